@@ -1,71 +1,169 @@
+use std::borrow::Cow;
 use std::env;
 use std::path::{PathBuf};
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, RecvTimeoutError};
 use std::time::Duration;
-use libtailor::Tailor;
+use tailor::{detect_parser, strip_ansi, LevelClassifier, LogLevel, Message, PipeClient, Query, RecordParser, Tailor, TimestampParser, TimestampTracker};
 
 use colored::*;
 
-enum MessageType {
-    Normal,
-    Debug,
-    Warning,
-    Error,
+const STDIN_ARG: &str = "-";
+/// Option adding level words, e.g. `--levels CRIT=fatal,35=warn`
+const LEVELS_ARG: &str = "--levels";
+/// Option printing only matching lines, e.g. `--filter 'level>=warn -healthcheck'`
+const FILTER_ARG: &str = "--filter";
+/// Option removing ANSI escape sequences, e.g. colours, from lines instead of passing them through
+const STRIP_ANSI_ARG: &str = "--strip-ansi";
+
+/// Query matched against lines as they come, records are parsed with format detected
+/// from the first batch of lines. Lines are matched without ANSI escape sequences.
+struct LineFilter {
+    query: Query,
+    parser: Option<Box<dyn RecordParser>>,
+    is_format_detected: bool,
+    timestamps: TimestampTracker,
 }
 
-fn get_message_type(msg: &String) -> MessageType {
-    if msg.contains("DEBUG") || msg.contains("debug") {
-        return MessageType::Debug;
+impl LineFilter {
+    fn new(query: Query) -> Self {
+        Self {
+            query,
+            parser: None,
+            is_format_detected: false,
+            timestamps: TimestampTracker::new(TimestampParser::new()),
+        }
     }
 
-    if msg.contains("WARNING") || msg.contains("WARN") || msg.contains("warning") {
-        return MessageType::Warning;
-    }
+    fn retain(&mut self, lines: Vec<String>) -> Vec<String> {
+        if !self.is_format_detected {
+            self.parser = detect_parser(&strip_lines(&lines));
+            self.is_format_detected = true;
+        }
 
-    if msg.contains("ERROR") || msg.contains("ERR") || msg.contains("error") || msg.contains("Error") {
-        return MessageType::Error;
+        lines.into_iter().filter(|line| {
+            let text = strip_ansi(line);
+            let record = self.parser.as_ref().and_then(|parser| parser.parse(&text));
+            let time = self.timestamps.push(&text, record.as_ref());
+            self.query.matches(&text, record.as_ref(), time)
+        }).collect()
     }
+}
 
-    MessageType::Normal
+fn strip_lines(lines: &[String]) -> Vec<String> {
+    lines.iter().map(|line| strip_ansi(line).into_owned()).collect()
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        println!("Specify path to file or directory as the argument.");
-        return;
+fn print_lines(lines: Vec<String>, levels: &LevelClassifier) {
+    for string in lines {
+        let level = match strip_ansi(&string) {
+            // Resets in lines coloured by escape sequences would cut level colours short
+            Cow::Owned(_) => None,
+            Cow::Borrowed(text) => levels.classify(text),
+        };
+        match level {
+            Some(LogLevel::Trace) | Some(LogLevel::Debug) => println!("{}", string.cyan()),
+            Some(LogLevel::Warning) => println!("{}", string.black().on_yellow()),
+            Some(LogLevel::Error) => println!("{}", string.black().on_red()),
+            Some(LogLevel::Fatal) => println!("{}", string.white().bold().on_red()),
+            _ => println!("{}", string),
+        }
     }
+}
 
-    let mut instance = Tailor::new();
-    Tailor::set_new_lines_callback(
-        Box::new(|client_id, msg_type, msg| {
-            if msg_type == tailor::MSG_TYPE_ADD_LINES {
-                for string in msg {
-                    match get_message_type(&string) {
-                        MessageType::Debug => println!("{}", string.cyan()),
-                        MessageType::Warning => println!("{}", string.black().on_yellow()),
-                        MessageType::Error => println!("{}", string.black().on_red()),
-                        _ => println!("{}", string),
-                    }
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut path = None;
+    let mut levels = LevelClassifier::default();
+    let mut filter = None;
+    let mut is_stripping_ansi = false;
+    while let Some(arg) = args.next() {
+        if arg == FILTER_ARG {
+            filter = match Query::parse(&args.next().unwrap_or_default()) {
+                Ok(query) => Some(query),
+                Err(msg) => {
+                    println!("Invalid {} value: {}", FILTER_ARG, msg);
+                    return;
                 }
-            }
-        })
-    );
+            };
+        } else if arg == STRIP_ANSI_ARG {
+            is_stripping_ansi = true;
+        } else if arg == LEVELS_ARG {
+            let spec = args.next().unwrap_or_default();
+            levels = match levels.with_levels(&spec) {
+                Ok(levels) => levels,
+                Err(msg) => {
+                    println!("Invalid {} value: {}", LEVELS_ARG, msg);
+                    return;
+                }
+            };
+        } else if path.is_none() {
+            path = Some(arg);
+        } else {
+            path = None;
+            break;
+        }
+    }
+
+    let path = match path {
+        Some(path) => path,
+        None => {
+            println!("Specify path to file or directory as the argument, or - to read standard input.");
+            println!("Extra level words can be given as {} CRIT=fatal,35=warn", LEVELS_ARG);
+            println!("Lines can be filtered with {} 'level>=warn -healthcheck'", FILTER_ARG);
+            println!("Colours and other ANSI escape sequences are passed through unless {} is given", STRIP_ANSI_ARG);
+            return;
+        }
+    };
+    let mut filter = filter.map(|query| LineFilter::new(query.with_levels(levels.clone())));
 
-    let watch_path = PathBuf::from(args[1].as_str());
-    let client_id = instance.watch(watch_path);
+    let mut instance = match Tailor::new() {
+        Ok(instance) => instance,
+        Err(msg) => {
+            println!("Failed to create Tailor instance: {}", msg);
+            return;
+        }
+    };
+
+    let (message_tx, message_rx) = channel();
+    let client_id = if path == STDIN_ARG {
+        instance.watch_source(Box::new(PipeClient::stdin()), message_tx)
+    } else {
+        instance.watch(PathBuf::from(path), message_tx)
+    };
 
     let (tx,rx) = channel();
     ctrlc::set_handler(move || {
         tx.send(()).expect("Failed to send Ctrl+C signal.");
     }).expect("Failed to set Ctrl+C handler.");
     loop {
-        match rx.recv_timeout(Duration::from_secs(3)) {
-            Ok(_) => {
-                instance.stop(client_id);
-                break;
+        if rx.try_recv().is_ok() {
+            instance.stop(client_id);
+            break;
+        }
+
+        match message_rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(Message::NewLines(mut lines)) => {
+                if is_stripping_ansi {
+                    lines = strip_lines(&lines);
+                }
+                let lines = match &mut filter {
+                    Some(filter) => filter.retain(lines),
+                    None => lines,
+                };
+                print_lines(lines, &levels);
+            },
+            Ok(Message::NewErrorLines(mut lines)) => {
+                if is_stripping_ansi {
+                    lines = strip_lines(&lines);
+                }
+                for string in lines {
+                    eprintln!("{}", string.red());
+                }
             },
-            Err(_) => ()
+            Ok(_) => (),
+            Err(RecvTimeoutError::Timeout) => (),
+            // Source has nothing more to read, e.g. standard input was closed
+            Err(RecvTimeoutError::Disconnected) => break,
         }
     }
 }
@@ -0,0 +1,218 @@
+use std::borrow::Cow;
+
+const ESC: char = '\x1b';
+
+/// Terminal colour: one of 256 indexed ones, the first 16 being the basic and bright ones, or RGB
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnsiColor {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl AnsiColor {
+    /// RGB value, indexed colours as in xterm
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        const BASIC: [(u8, u8, u8); 16] = [
+            (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+            (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+            (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+            (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+        ];
+        match self {
+            AnsiColor::Rgb(r, g, b) => (r, g, b),
+            AnsiColor::Indexed(idx @ 0..=15) => BASIC[idx as usize],
+            AnsiColor::Indexed(idx @ 16..=231) => {
+                let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+                let idx = idx - 16;
+                (level(idx / 36), level(idx / 6 % 6), level(idx % 6))
+            },
+            AnsiColor::Indexed(idx) => {
+                let gray = 8 + (idx - 232) * 10;
+                (gray, gray, gray)
+            },
+        }
+    }
+}
+
+/// Text style set by SGR escape sequences, none colours for the default ones
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AnsiStyle {
+    pub foreground: Option<AnsiColor>,
+    pub background: Option<AnsiColor>,
+    pub is_bold: bool,
+    pub is_italic: bool,
+    pub is_underline: bool,
+}
+
+impl AnsiStyle {
+    /// Apply parameters of SGR sequence, e.g. `1;31` of `ESC[1;31m`
+    fn apply(&mut self, parameters: &str) {
+        let mut codes = parameters.split([';', ':']).map(|code| code.parse::<u16>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = AnsiStyle::default(),
+                1 => self.is_bold = true,
+                3 => self.is_italic = true,
+                4 => self.is_underline = true,
+                22 => self.is_bold = false,
+                23 => self.is_italic = false,
+                24 => self.is_underline = false,
+                30..=37 => self.foreground = Some(AnsiColor::Indexed(code as u8 - 30)),
+                38 => self.foreground = parse_extended_color(&mut codes),
+                39 => self.foreground = None,
+                40..=47 => self.background = Some(AnsiColor::Indexed(code as u8 - 40)),
+                48 => self.background = parse_extended_color(&mut codes),
+                49 => self.background = None,
+                90..=97 => self.foreground = Some(AnsiColor::Indexed(code as u8 - 90 + 8)),
+                100..=107 => self.background = Some(AnsiColor::Indexed(code as u8 - 100 + 8)),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Colour following 38 or 48 code: `5;index` or `2;r;g;b`
+fn parse_extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<AnsiColor> {
+    match codes.next()? {
+        5 => Some(AnsiColor::Indexed(codes.next()? as u8)),
+        2 => Some(AnsiColor::Rgb(codes.next()? as u8, codes.next()? as u8, codes.next()? as u8)),
+        _ => None,
+    }
+}
+
+/// Styled part of text stripped of escape sequences, as byte range
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnsiSpan {
+    pub start: usize,
+    pub end: usize,
+    pub style: AnsiStyle,
+}
+
+/// Text without escape sequences, and its parts styled by SGR ones. Owned text without
+/// escape sequences is returned as is.
+pub fn parse_ansi<'a>(text: impl Into<Cow<'a, str>>) -> (String, Vec<AnsiSpan>) {
+    let text = text.into();
+    if !text.contains(ESC) {
+        return (text.into_owned(), vec![]);
+    }
+
+    let mut stripped = String::with_capacity(text.len());
+    let mut spans = vec![];
+    let mut style = AnsiStyle::default();
+    let mut style_start = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            stripped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // Control sequence: parameters and intermediate bytes, then final byte
+            Some('[') => {
+                let mut parameters = String::new();
+                let mut final_byte = None;
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        final_byte = Some(c);
+                        break;
+                    }
+                    parameters.push(c);
+                }
+                if final_byte == Some('m') {
+                    let mut new_style = style;
+                    new_style.apply(&parameters);
+                    if new_style != style {
+                        if style != AnsiStyle::default() && style_start < stripped.len() {
+                            spans.push(AnsiSpan { start: style_start, end: stripped.len(), style });
+                        }
+                        style = new_style;
+                        style_start = stripped.len();
+                    }
+                }
+            },
+            // Operating system command, e.g. window title, ended by BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == ESC {
+                        chars.next_if_eq(&'\\');
+                        break;
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+    if style != AnsiStyle::default() && style_start < stripped.len() {
+        spans.push(AnsiSpan { start: style_start, end: stripped.len(), style });
+    }
+
+    (stripped, spans)
+}
+
+/// Text without escape sequences, borrowed when there are none
+pub fn strip_ansi(text: &str) -> Cow<'_, str> {
+    if text.contains(ESC) {
+        Cow::Owned(parse_ansi(text).0)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ansi::{parse_ansi, strip_ansi, AnsiColor, AnsiSpan, AnsiStyle};
+
+    #[test]
+    fn plain_text() {
+        assert_eq!(parse_ansi("no escapes"), ("no escapes".to_string(), vec![]));
+        let owned = "no escapes".to_string();
+        let pointer = owned.as_ptr();
+        assert_eq!(parse_ansi(owned).0.as_ptr(), pointer);
+        assert_eq!(strip_ansi("no escapes"), "no escapes");
+    }
+
+    #[test]
+    fn colors() {
+        let (text, spans) = parse_ansi("\x1b[31merror\x1b[0m: \x1b[1;38;5;208mdisk\x1b[39m full\x1b[m");
+        assert_eq!(text, "error: disk full");
+        assert_eq!(spans, vec![
+            AnsiSpan { start: 0, end: 5, style: AnsiStyle { foreground: Some(AnsiColor::Indexed(1)), ..Default::default() } },
+            AnsiSpan { start: 7, end: 11, style: AnsiStyle { foreground: Some(AnsiColor::Indexed(208)), is_bold: true, ..Default::default() } },
+            AnsiSpan { start: 11, end: 16, style: AnsiStyle { is_bold: true, ..Default::default() } },
+        ]);
+    }
+
+    #[test]
+    fn rgb_and_bright_colors() {
+        let (text, spans) = parse_ansi("\x1b[48;2;10;20;30m\x1b[93mwarn");
+        assert_eq!(text, "warn");
+        assert_eq!(spans, vec![AnsiSpan {
+            start: 0,
+            end: 4,
+            style: AnsiStyle {
+                foreground: Some(AnsiColor::Indexed(11)),
+                background: Some(AnsiColor::Rgb(10, 20, 30)),
+                ..Default::default()
+            },
+        }]);
+    }
+
+    #[test]
+    fn other_sequences_stripped() {
+        assert_eq!(strip_ansi("\x1b]0;title\x07\x1b[2Kdone\x1b[1A"), "done");
+        assert_eq!(strip_ansi("\x1b]8;;http://x\x1b\\link\x1b]8;;\x1b\\"), "link");
+        assert_eq!(strip_ansi("cut \x1b["), "cut ");
+    }
+
+    #[test]
+    fn indexed_to_rgb() {
+        assert_eq!(AnsiColor::Indexed(1).to_rgb(), (205, 0, 0));
+        assert_eq!(AnsiColor::Indexed(16).to_rgb(), (0, 0, 0));
+        assert_eq!(AnsiColor::Indexed(231).to_rgb(), (255, 255, 255));
+        assert_eq!(AnsiColor::Indexed(232).to_rgb(), (8, 8, 8));
+    }
+}
@@ -0,0 +1,186 @@
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use crate::{Message, MessageSender};
+use crate::client::LineSource;
+use crate::linereader::LineReader;
+
+const POLL_TIMEOUT_MS: u64 = 100;
+const MIN_RESTART_BACKOFF_MS: u64 = 1000;
+const MAX_RESTART_BACKOFF_MS: u64 = 30000;
+
+/// Runs shell command and follows its output, e.g. `journalctl -f` or `docker logs -f`
+///
+/// Stdout lines are reported as `Message::NewLines` and stderr lines as `Message::NewErrorLines`.
+/// Exit of the command is reported with `Message::Exited`.
+pub struct CommandClient {
+    command: String,
+    restart: bool,
+}
+
+impl CommandClient {
+    pub fn new(command: &str) -> Self {
+        CommandClient {
+            command: command.to_string(),
+            restart: false,
+        }
+    }
+
+    /// Run command again once it exits. Restarts are delayed with exponential backoff.
+    pub fn with_restart(mut self, restart: bool) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    fn spawn(&self) -> std::io::Result<Child> {
+        let mut command = if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.arg("/C");
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c");
+            command
+        };
+
+        command.arg(&self.command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+    }
+
+    /// Follow output of running command until it exits. Returns false if owner requested stop.
+    fn follow(&self, mut child: Child, owner_rx: &Receiver<bool>, message_tx: &MessageSender) -> bool {
+        let (tx,rx) = channel();
+        if let Some(stdout) = child.stdout.take() {
+            let mut reader = LineReader::new(stdout, tx.clone());
+            std::thread::spawn(move || {
+                if let Err(error) = reader.read() { log::error!("{}", error) }
+            });
+        }
+        if let Some(stderr) = child.stderr.take() {
+            let mut reader = LineReader::with_message(stderr, tx, Message::NewErrorLines);
+            std::thread::spawn(move || {
+                if let Err(error) = reader.read() { log::error!("{}", error) }
+            });
+        }
+
+        loop {
+            match rx.recv_timeout(Duration::from_millis(POLL_TIMEOUT_MS)) {
+                Ok(msg) => {
+                    if message_tx.send(msg).is_err() {
+                        log::error!("Failed to send command output to owner.");
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) => {},
+                // Both output pipes are closed, command has finished
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            if let Ok(true) = owner_rx.try_recv() {
+                if child.kill().is_err() {
+                    log::warn!("Failed to kill command: {}", self.command);
+                }
+                let _ = child.wait();
+                return false;
+            }
+        }
+
+        let code = match child.wait() {
+            Ok(status) => status.code(),
+            Err(error) => {
+                log::error!("Failed to wait for command {}: {}", self.command, error);
+                None
+            }
+        };
+        if message_tx.send(Message::Exited(code)).is_err() {
+            log::error!("Failed to send command exit status to owner.");
+        }
+
+        true
+    }
+
+    /// Sleep for given time unless owner requests stop. Returns false if owner requested stop.
+    fn wait_restart(owner_rx: &Receiver<bool>, backoff: Duration) -> bool {
+        matches!(owner_rx.recv_timeout(backoff), Err(RecvTimeoutError::Timeout))
+    }
+}
+
+impl LineSource for CommandClient {
+    fn start(&mut self, _client_id: i32, owner_rx: Receiver<bool>, message_tx: MessageSender) {
+        let mut backoff = Duration::from_millis(MIN_RESTART_BACKOFF_MS);
+        loop {
+            let started_at = Instant::now();
+            log::info!("Running command {}", self.command);
+            match self.spawn() {
+                Ok(child) => {
+                    if !self.follow(child, &owner_rx, &message_tx) {
+                        break;
+                    }
+                },
+                Err(error) => {
+                    let msg = format!("Failed to run command {}: {}", self.command, error);
+                    log::error!("{}", msg);
+                    if message_tx.send(Message::NewErrorLines(vec![msg])).is_err() {
+                        break;
+                    }
+                }
+            }
+
+            if !self.restart {
+                break;
+            }
+
+            // Command which ran for a while is not crashing in a loop, start over with short delay
+            if started_at.elapsed() > Duration::from_millis(MAX_RESTART_BACKOFF_MS) {
+                backoff = Duration::from_millis(MIN_RESTART_BACKOFF_MS);
+            }
+            if !Self::wait_restart(&owner_rx, backoff) {
+                break;
+            }
+            backoff = std::cmp::min(backoff * 2, Duration::from_millis(MAX_RESTART_BACKOFF_MS));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc::{channel, Receiver};
+    use std::time::Duration;
+    use crate::{CommandClient, Message, Tailor};
+
+    const WAIT_TIMEOUT_MS: u64 = 3000;
+
+    fn recv(rx: &Receiver<Message>) -> Message {
+        rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS)).expect("Expected message from command")
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stdout_stderr_and_exit() {
+        let mut tailor = Tailor::new().unwrap();
+        let (tx, rx) = channel();
+        let command = CommandClient::new("echo out; sleep 0.2; echo err 1>&2; sleep 0.2; exit 3");
+        let _client_id = tailor.watch_source(Box::new(command), tx);
+
+        assert!(matches!(recv(&rx), Message::NewLines(lines) if lines == vec!["out"]));
+        assert!(matches!(recv(&rx), Message::NewErrorLines(lines) if lines == vec!["err"]));
+        assert!(matches!(recv(&rx), Message::Exited(Some(3))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restart() {
+        let mut tailor = Tailor::new().unwrap();
+        let (tx, rx) = channel();
+        let command = CommandClient::new("echo run").with_restart(true);
+        let client_id = tailor.watch_source(Box::new(command), tx);
+
+        for _ in 0..2 {
+            assert!(matches!(recv(&rx), Message::NewLines(lines) if lines == vec!["run"]));
+            assert!(matches!(recv(&rx), Message::Exited(Some(0))));
+        }
+        tailor.stop(client_id);
+    }
+}
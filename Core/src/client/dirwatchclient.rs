@@ -2,43 +2,28 @@ use std::path::PathBuf;
 use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
 use std::thread::JoinHandle;
 use std::time::Duration;
-use crate::{DirWatch, FileWatch, Message};
-use crate::client::WatchClient;
+use crate::{DirWatch, FileWatch, Message, MessageSender};
+use crate::client::LineSource;
 
 pub struct DirWatchClient {
-    owner_rx: Receiver<bool>,
-    message_tx: Sender<Message>,
-    watchable_tx: Sender<bool>,
-    watchable_rx: Receiver<PathBuf>,
-    _watchable_thread: JoinHandle<()>,
+    path: PathBuf,
     file_watchable_rx: Option<Receiver<Message>>,
     file_watchable_tx: Option<Sender<bool>>,
     file_watchable_thread: Option<JoinHandle<()>>,
 }
 
 impl DirWatchClient {
-    pub fn new(path: PathBuf, owner_rx: Receiver<bool>, message_tx: Sender<Message>) -> Self {
-        let (tx,rx) = channel();
-        let mut watchable = DirWatch::new(path.as_path(), tx);
-        let watchable_tx = watchable.get_tx();
-        let thread = std::thread::spawn(move || {
-            if watchable.watch().is_err() { log::error!("Failed to start watching directory {}.", path.to_str().unwrap_or("UNKNOWN")) }
-        });
-
+    pub fn new(path: PathBuf) -> Self {
         DirWatchClient {
-            owner_rx,
-            message_tx,
-            watchable_tx,
-            watchable_rx: rx,
-            _watchable_thread: thread,
+            path,
             file_watchable_rx: None,
             file_watchable_tx: None,
             file_watchable_thread: None,
         }
     }
 
-    fn start_filewatch(&mut self, _client_id: i32, path: PathBuf) {
-        if self.message_tx.send(Message::NewFile(path.clone())).is_err() {
+    fn start_filewatch(&mut self, _client_id: i32, path: PathBuf, message_tx: &MessageSender) {
+        if message_tx.send(Message::NewFile(path.clone())).is_err() {
             log::error!("Failed to send message NewFile to owner.");
         }
 
@@ -69,28 +54,26 @@ impl DirWatchClient {
     }
 }
 
-impl Drop for DirWatchClient {
-    fn drop(&mut self) {
-        self.kill_current_file_watchable();
-        if self.watchable_tx.send(true).is_err()
-        {
-            log::warn!("Failed to send stop to watchable");
-        }
-    }
-}
+impl LineSource for DirWatchClient {
+    fn start(&mut self, client_id: i32, owner_rx: Receiver<bool>, message_tx: MessageSender) {
+        let (tx,watchable_rx) = channel();
+        let path = self.path.clone();
+        let mut watchable = DirWatch::new(path.as_path(), tx);
+        let watchable_tx = watchable.get_tx();
+        let _watchable_thread = std::thread::spawn(move || {
+            if watchable.watch().is_err() { log::error!("Failed to start watching directory {}.", path.to_str().unwrap_or("UNKNOWN")) }
+        });
 
-impl WatchClient for DirWatchClient {
-    fn start(&mut self, client_id: i32) {
         loop {
-            if let Ok(msg) = self.watchable_rx.try_recv() {
+            if let Ok(msg) = watchable_rx.try_recv() {
                 self.kill_current_file_watchable();
-                self.start_filewatch(client_id, msg);
+                self.start_filewatch(client_id, msg, &message_tx);
             }
 
             if let Some(rx) = self.file_watchable_rx.as_ref() {
                 match rx.recv_timeout(Duration::from_secs(1)) {
                     Ok(msg) => {
-                        if self.message_tx.send(msg).is_err() {
+                        if message_tx.send(msg).is_err() {
                             log::error!("Dir warch client failed to send message to owner.");
                         }
                     },
@@ -102,11 +85,17 @@ impl WatchClient for DirWatchClient {
                 }
             }
 
-            if let Ok(msg) = self.owner_rx.try_recv() {
+            if let Ok(msg) = owner_rx.try_recv() {
                 if msg {
                     break;
                 }
             }
         }
+
+        self.kill_current_file_watchable();
+        if watchable_tx.send(true).is_err()
+        {
+            log::warn!("Failed to send stop to watchable");
+        }
     }
 }
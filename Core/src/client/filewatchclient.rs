@@ -1,67 +1,52 @@
 use std::path::PathBuf;
-use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
-use std::thread::JoinHandle;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
 use std::time::Duration;
-use crate::{FileWatch, Message};
-use crate::client::WatchClient;
+use crate::{FileWatch, MessageSender};
+use crate::client::LineSource;
 
 pub struct FileWatchClient {
-    owner_rx: Receiver<bool>,
-    message_tx: Sender<Message>,
-    _watchable_thread: JoinHandle<()>,
-    watchable_rx: Receiver<Message>,
-    watchable_tx: Sender<bool>,
+    path: PathBuf,
 }
 
 impl FileWatchClient {
-    pub fn new(path: PathBuf, owner_rx: Receiver<bool>, message_tx: Sender<Message>) -> Self {
-        let (tx,rx) = channel();
-        let mut watchable = FileWatch::new(path.as_path(), tx);
-        let watchable_tx = watchable.get_tx();
-        let thread = std::thread::spawn(move || {
-            if watchable.watch().is_err() { log::error!("Failed to start watching file {}.", path.to_str().unwrap_or("UNKNOWN")) }
-        });
-
+    pub fn new(path: PathBuf) -> Self {
         FileWatchClient {
-            owner_rx,
-            message_tx,
-            _watchable_thread: thread,
-            watchable_rx: rx,
-            watchable_tx,
+            path,
         }
     }
 }
 
-impl Drop for FileWatchClient {
-    fn drop(&mut self) {
-        if self.watchable_tx.send(true).is_err() {
-            log::warn!("Failed to send stop to file watchable.");
-        }
-    }
-}
+impl LineSource for FileWatchClient {
+    fn start(&mut self, _client_id: i32, owner_rx: Receiver<bool>, message_tx: MessageSender) {
+        let (tx,rx) = channel();
+        let path = self.path.clone();
+        let mut watchable = FileWatch::new(path.as_path(), tx);
+        let watchable_tx = watchable.get_tx();
+        let _watchable_thread = std::thread::spawn(move || {
+            if watchable.watch().is_err() { log::error!("Failed to start watching file {}.", path.to_str().unwrap_or("UNKNOWN")) }
+        });
 
-impl WatchClient for FileWatchClient {
-    fn start(&mut self, _client_id: i32) {
         loop {
-            match self.watchable_rx.recv_timeout(Duration::from_secs(1)) {
+            match rx.recv_timeout(Duration::from_secs(1)) {
                 Ok(msg) => {
-                    if self.message_tx.send(msg).is_err() {
+                    if message_tx.send(msg).is_err() {
                         log::error!("Failed to send new lines to owner.");
                     }
                 },
                 Err(RecvTimeoutError::Timeout) => {},
                 Err(RecvTimeoutError::Disconnected) => {
-                    log::info!("FileWatchClient exited.")
+                    log::info!("FileWatchClient exited.");
+                    break;
                 }
             }
 
-            if let Ok(msg) = self.owner_rx.try_recv() {
+            if let Ok(msg) = owner_rx.try_recv() {
                 if msg {
                     break;
                 }
             }
         }
 
-        if self.watchable_tx.send(true).is_err() { log::error!("Failed to send kill message to watchable_tx.") }
+        if watchable_tx.send(true).is_err() { log::warn!("Failed to send kill message to watchable_tx.") }
     }
 }
@@ -1,6 +1,51 @@
+use std::sync::mpsc::Receiver;
+use crate::MessageSender;
+
 pub mod filewatchclient;
 pub mod dirwatchclient;
+pub mod commandclient;
+pub mod pipeclient;
+pub mod syslogclient;
+
+/// Source of lines for a single watch.
+///
+/// Tailor runs every source on a dedicated thread by calling `start`. Implementations send
+/// `Message`s through `message_tx` until owner sends `true` through `owner_rx` or the source
+/// has nothing more to produce. Implement it to feed Tailor from anything other than files.
+pub trait LineSource: Send {
+    fn start(&mut self, client_id: i32, owner_rx: Receiver<bool>, message_tx: MessageSender);
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc::{channel, Receiver};
+    use std::time::Duration;
+    use crate::{LineSource, Message, MessageSender, Tailor};
+
+    const WAIT_TIMEOUT_MS: u64 = 1000;
+
+    struct VecSource {
+        lines: Vec<String>,
+    }
+
+    impl LineSource for VecSource {
+        fn start(&mut self, _client_id: i32, owner_rx: Receiver<bool>, message_tx: MessageSender) {
+            assert!(message_tx.send(Message::NewLines(self.lines.clone())).is_ok());
+            let _ = owner_rx.recv();
+        }
+    }
+
+    #[test]
+    fn watch_custom_source() {
+        let mut tailor = Tailor::new().unwrap();
+        let (tx, rx) = channel();
+        let lines = vec![String::from("Line1"), String::from("Line2")];
+        let client_id = tailor.watch_source(Box::new(VecSource { lines: lines.clone() }), tx);
 
-pub trait WatchClient {
-    fn start(&mut self, client_id: i32);
-}
\ No newline at end of file
+        match rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS)) {
+            Ok(Message::NewLines(received)) => assert_eq!(received, lines),
+            _ => panic!("Expected lines from custom source"),
+        }
+        tailor.stop(client_id);
+    }
+}
@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::Duration;
+use crate::MessageSender;
+use crate::client::LineSource;
+use crate::linereader::LineReader;
+
+const POLL_TIMEOUT_MS: u64 = 100;
+
+/// Follows lines from standard input or a named pipe (FIFO)
+///
+/// Pipes can not be seeked and have no length, so unlike `FileWatchClient` lines are read
+/// as they arrive. Named pipe is reopened when writer closes it, standard input is read once.
+pub struct PipeClient {
+    path: Option<PathBuf>,
+}
+
+impl PipeClient {
+    pub fn stdin() -> Self {
+        PipeClient {
+            path: None,
+        }
+    }
+
+    pub fn fifo(path: PathBuf) -> Self {
+        PipeClient {
+            path: Some(path),
+        }
+    }
+
+    /// Check whether path points to a named pipe
+    pub fn is_fifo(path: &Path) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            std::fs::metadata(path).map(|metadata| metadata.file_type().is_fifo()).unwrap_or(false)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            false
+        }
+    }
+
+    fn open(&self) -> Result<Box<dyn Read + Send>, String> {
+        match &self.path {
+            None => Ok(Box::new(std::io::stdin())),
+            // Blocks until there is a writer on the other end of the pipe
+            Some(path) => match File::open(path) {
+                Ok(file) => Ok(Box::new(file)),
+                Err(error) => Err(format!("Could not open pipe for reading: {}. Error: {}", path.to_str().unwrap_or("Unknown pipe path"), error)),
+            },
+        }
+    }
+}
+
+impl LineSource for PipeClient {
+    fn start(&mut self, _client_id: i32, owner_rx: Receiver<bool>, message_tx: MessageSender) {
+        let (tx,rx) = channel();
+        let pipe = PipeClient { path: self.path.clone() };
+        let reopen = self.path.is_some();
+        // Reading can not be interrupted, so reader thread is left behind if owner stops the watch.
+        // It exits as soon as the pipe delivers more data or gets closed.
+        let _reader_thread = std::thread::spawn(move || {
+            loop {
+                let reader = match pipe.open() {
+                    Ok(reader) => reader,
+                    Err(error) => {
+                        log::error!("{}", error);
+                        break;
+                    }
+                };
+                if let Err(error) = LineReader::new(reader, tx.clone()).read() {
+                    log::error!("{}", error);
+                    break;
+                }
+                if !reopen {
+                    break;
+                }
+            }
+        });
+
+        loop {
+            match rx.recv_timeout(Duration::from_millis(POLL_TIMEOUT_MS)) {
+                Ok(msg) => {
+                    if message_tx.send(msg).is_err() {
+                        log::error!("Failed to send piped lines to owner.");
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) => {},
+                Err(RecvTimeoutError::Disconnected) => {
+                    log::info!("PipeClient exited.");
+                    break;
+                }
+            }
+
+            if let Ok(true) = owner_rx.try_recv() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process::Command;
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+    use crate::{Message, PipeClient, Tailor};
+
+    const TEST_DIR: &str = "./test/pipeclient";
+    const WAIT_TIMEOUT_MS: u64 = 3000;
+
+    fn create_fifo(name: &str) -> PathBuf {
+        let mut fifo_path = PathBuf::from(TEST_DIR);
+        assert!(std::fs::create_dir_all(&fifo_path).is_ok());
+        fifo_path.push(name);
+        let _ = std::fs::remove_file(&fifo_path);
+        assert!(Command::new("mkfifo").arg(&fifo_path).status().unwrap().success());
+
+        fifo_path
+    }
+
+    #[test]
+    fn read_fifo() {
+        let fifo_path = create_fifo("read_fifo");
+        assert!(PipeClient::is_fifo(&fifo_path));
+
+        let mut tailor = Tailor::new().unwrap();
+        let (tx, rx) = channel();
+        let client_id = tailor.watch(fifo_path.clone(), tx);
+
+        for content in ["Line1\nLine2\n", "Line3\n"] {
+            let mut writer = OpenOptions::new().write(true).open(&fifo_path).unwrap();
+            assert!(writer.write_all(content.as_bytes()).is_ok());
+            drop(writer);
+
+            let expected: Vec<&str> = content.lines().collect();
+            match rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS)) {
+                Ok(Message::NewLines(lines)) => assert_eq!(lines, expected),
+                _ => panic!("Expected lines from pipe"),
+            }
+        }
+
+        tailor.stop(client_id);
+        let _ = std::fs::remove_file(&fifo_path);
+    }
+}
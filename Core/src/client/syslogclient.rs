@@ -0,0 +1,290 @@
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::{Message, MessageSender, SyslogMessage};
+use crate::client::LineSource;
+
+const POLL_TIMEOUT_MS: u64 = 100;
+const MAX_DATAGRAM_SIZE: usize = 65535;
+/// Octet counted frames claiming more bytes are rejected, closing the connection
+const MAX_FRAME_SIZE: usize = 64 * 1024;
+const MAX_BATCH_LINES: usize = 1000;
+
+enum Listener {
+    Udp(UdpSocket),
+    Tcp(TcpListener),
+}
+
+/// Receives syslog messages over the network, in place of a syslog daemon writing them to file
+///
+/// Both RFC 3164 and RFC 5424 messages are accepted. TCP connections may use either octet
+/// counting or newline delimited framing (RFC 6587). Every message is reported as a line
+/// formatted with `SyslogMessage::to_line`, frames which can not be parsed are reported as is.
+pub struct SyslogClient {
+    listener: Listener,
+}
+
+impl SyslogClient {
+    /// Listen for syslog datagrams on given address, e.g. `127.0.0.1:514`
+    pub fn udp<A: ToSocketAddrs>(addr: A) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(Duration::from_millis(POLL_TIMEOUT_MS)))?;
+        Ok(SyslogClient {
+            listener: Listener::Udp(socket),
+        })
+    }
+
+    /// Accept syslog TCP connections on given address, e.g. `127.0.0.1:601`
+    pub fn tcp<A: ToSocketAddrs>(addr: A) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(SyslogClient {
+            listener: Listener::Tcp(listener),
+        })
+    }
+
+    /// Address the client listens on. Useful when bound to port 0.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        match &self.listener {
+            Listener::Udp(socket) => socket.local_addr(),
+            Listener::Tcp(listener) => listener.local_addr(),
+        }
+    }
+
+    fn frame_to_line(frame: &[u8]) -> String {
+        let frame = String::from_utf8_lossy(frame);
+        match SyslogMessage::parse(&frame) {
+            Some(msg) => msg.to_line(),
+            None => frame.trim_end().to_string(),
+        }
+    }
+
+    fn receive_udp(socket: &UdpSocket, owner_rx: &Receiver<bool>, message_tx: &MessageSender) {
+        let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+        let mut lines_to_send = vec![];
+        loop {
+            match socket.recv_from(&mut buf) {
+                Ok((len, _)) => {
+                    lines_to_send.push(Self::frame_to_line(&buf[..len]));
+                    if lines_to_send.len() < MAX_BATCH_LINES {
+                        // Collect datagrams which arrived together into a single message
+                        let _ = socket.set_nonblocking(true);
+                        continue;
+                    }
+                },
+                Err(error) if error.kind() == ErrorKind::WouldBlock || error.kind() == ErrorKind::TimedOut => (),
+                Err(error) => {
+                    log::error!("Failed to receive syslog datagram: {}", error);
+                    break;
+                }
+            }
+
+            let _ = socket.set_nonblocking(false);
+            if !lines_to_send.is_empty() && message_tx.send(Message::NewLines(std::mem::take(&mut lines_to_send))).is_err() {
+                log::error!("Failed to send syslog lines to owner.");
+            }
+
+            if let Ok(true) = owner_rx.try_recv() {
+                break;
+            }
+        }
+    }
+
+    fn receive_tcp(listener: &TcpListener, owner_rx: &Receiver<bool>, message_tx: &MessageSender) {
+        let (tx,rx) = channel();
+        let should_join = Arc::new(AtomicBool::new(false));
+        loop {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    log::info!("Accepted syslog connection from {}", peer);
+                    let tx = tx.clone();
+                    let should_join = should_join.clone();
+                    std::thread::spawn(move || Self::read_connection(stream, tx, should_join));
+                },
+                Err(error) if error.kind() == ErrorKind::WouldBlock => (),
+                Err(error) => log::error!("Failed to accept syslog connection: {}", error),
+            }
+
+            match rx.recv_timeout(Duration::from_millis(POLL_TIMEOUT_MS)) {
+                Ok(line) => {
+                    let mut lines_to_send = vec![line];
+                    while let Ok(line) = rx.try_recv() {
+                        lines_to_send.push(line);
+                        if lines_to_send.len() >= MAX_BATCH_LINES {
+                            break;
+                        }
+                    }
+                    if message_tx.send(Message::NewLines(lines_to_send)).is_err() {
+                        log::error!("Failed to send syslog lines to owner.");
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => (),
+            }
+
+            if let Ok(true) = owner_rx.try_recv() {
+                break;
+            }
+        }
+
+        should_join.store(true, Ordering::Relaxed);
+    }
+
+    fn read_connection(stream: TcpStream, tx: Sender<String>, should_join: Arc<AtomicBool>) {
+        if stream.set_nonblocking(false).is_err() || stream.set_read_timeout(Some(Duration::from_millis(POLL_TIMEOUT_MS))).is_err() {
+            log::error!("Failed to configure syslog connection.");
+            return;
+        }
+
+        let mut reader = BufReader::new(stream);
+        let mut frame = vec![];
+        while !should_join.load(Ordering::Relaxed) {
+            match Self::read_frame(&mut reader, &mut frame) {
+                Ok(true) => {
+                    if tx.send(Self::frame_to_line(&frame)).is_err() {
+                        break;
+                    }
+                    frame.clear();
+                },
+                Ok(false) => break,
+                Err(error) if error.kind() == ErrorKind::WouldBlock || error.kind() == ErrorKind::TimedOut => (),
+                Err(error) => {
+                    log::error!("Failed to read syslog connection: {}", error);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Read single frame, either octet counted (`LEN SP MSG`) or terminated by newline.
+    /// Returns false when connection is closed, and error for frames over `MAX_FRAME_SIZE`.
+    fn read_frame(reader: &mut BufReader<TcpStream>, frame: &mut Vec<u8>) -> std::io::Result<bool> {
+        let octet_counted = frame.is_empty() && reader.fill_buf()?.first().is_some_and(|b| b.is_ascii_digit());
+        if octet_counted || frame.first().is_some_and(|b| b.is_ascii_digit()) {
+            let max_len_digits = MAX_FRAME_SIZE.ilog10() as usize + 1;
+            if !frame.contains(&b' ') {
+                let limit = (max_len_digits + 1).saturating_sub(frame.len());
+                reader.by_ref().take(limit as u64).read_until(b' ', frame)?;
+                if !frame.ends_with(b" ") {
+                    if frame.len() > max_len_digits {
+                        return Err(std::io::Error::new(ErrorKind::InvalidData, "syslog frame length is too long"));
+                    }
+                    return Ok(false);
+                }
+            }
+            let len_end = frame.iter().position(|b| *b == b' ').unwrap_or(0);
+            let len = std::str::from_utf8(&frame[..len_end]).ok().and_then(|len| len.parse::<usize>().ok()).unwrap_or(0);
+            if len > MAX_FRAME_SIZE {
+                let error = format!("syslog frame of {} bytes exceeds {} bytes", len, MAX_FRAME_SIZE);
+                return Err(std::io::Error::new(ErrorKind::InvalidData, error));
+            }
+            let missing = (len_end + 1 + len).saturating_sub(frame.len());
+            let read = reader.by_ref().take(missing as u64).read_to_end(frame)?;
+            if read < missing {
+                return Ok(false);
+            }
+            frame.drain(..=len_end);
+            return Ok(true);
+        }
+
+        // Frame without trailing newline is only returned at the end of connection
+        Ok(reader.read_until(b'\n', frame)? != 0)
+    }
+}
+
+impl LineSource for SyslogClient {
+    fn start(&mut self, _client_id: i32, owner_rx: Receiver<bool>, message_tx: MessageSender) {
+        match &self.listener {
+            Listener::Udp(socket) => Self::receive_udp(socket, &owner_rx, &message_tx),
+            Listener::Tcp(listener) => Self::receive_tcp(listener, &owner_rx, &message_tx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use std::net::{TcpStream, UdpSocket};
+    use std::sync::mpsc::{channel, Receiver};
+    use std::time::Duration;
+    use crate::{Message, SyslogClient, Tailor};
+
+    const WAIT_TIMEOUT_MS: u64 = 3000;
+
+    fn recv_lines(rx: &Receiver<Message>, count: usize) -> Vec<String> {
+        let mut received = vec![];
+        while received.len() < count {
+            match rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS)) {
+                Ok(Message::NewLines(lines)) => received.extend(lines),
+                _ => panic!("Expected syslog lines"),
+            }
+        }
+        received
+    }
+
+    #[test]
+    fn receive_udp() {
+        let client = SyslogClient::udp("127.0.0.1:0").unwrap();
+        let addr = client.local_addr().unwrap();
+        let mut tailor = Tailor::new().unwrap();
+        let (tx, rx) = channel();
+        let client_id = tailor.watch_source(Box::new(client), tx);
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        assert!(socket.send_to(b"<34>Oct 11 22:14:15 router sshd[42]: Login failed", addr).is_ok());
+        assert!(socket.send_to(b"<165>1 2003-10-11T22:14:15.003Z switch app - - - Port up", addr).is_ok());
+
+        assert_eq!(recv_lines(&rx, 2), vec![
+            "Oct 11 22:14:15 router auth.crit sshd: Login failed",
+            "2003-10-11T22:14:15.003Z switch local4.notice app: Port up",
+        ]);
+        tailor.stop(client_id);
+    }
+
+    #[test]
+    fn receive_tcp() {
+        let client = SyslogClient::tcp("127.0.0.1:0").unwrap();
+        let addr = client.local_addr().unwrap();
+        let mut tailor = Tailor::new().unwrap();
+        let (tx, rx) = channel();
+        let client_id = tailor.watch_source(Box::new(client), tx);
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert!(stream.write_all(b"<13>Oct  1 08:00:00 host1 app: newline framed\n").is_ok());
+        let octet_counted = "<11>1 - host2 app - - - octet counted\nwith newline";
+        assert!(stream.write_all(format!("{} {}", octet_counted.len(), octet_counted).as_bytes()).is_ok());
+        assert!(stream.flush().is_ok());
+
+        assert_eq!(recv_lines(&rx, 2), vec![
+            "Oct  1 08:00:00 host1 user.notice app: newline framed",
+            "- host2 user.err app: octet counted\nwith newline",
+        ]);
+        tailor.stop(client_id);
+    }
+
+    #[test]
+    fn reject_oversized_frame() {
+        let client = SyslogClient::tcp("127.0.0.1:0").unwrap();
+        let addr = client.local_addr().unwrap();
+        let mut tailor = Tailor::new().unwrap();
+        let (tx, rx) = channel();
+        let client_id = tailor.watch_source(Box::new(client), tx);
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert!(stream.write_all(b"99999999999 <11>1 - host1 app - - - never sent").is_ok());
+        assert!(stream.flush().is_ok());
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert!(stream.write_all(b"70000 <11>1 - host2 app - - - never sent").is_ok());
+        assert!(stream.flush().is_ok());
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        assert!(stream.write_all(b"<13>Oct  1 08:00:00 host3 app: still received\n").is_ok());
+        assert!(stream.flush().is_ok());
+
+        assert_eq!(recv_lines(&rx, 1), vec!["Oct  1 08:00:00 host3 user.notice app: still received"]);
+        tailor.stop(client_id);
+    }
+}
@@ -123,7 +123,11 @@ impl FileWatch {
             Ok(x) => {
                 self.file_size = x.len();
                 if self.read_offset > self.file_size {
+                    // File got truncated, it is read again from the start
                     self.read_offset = 0;
+                    if self.message_tx.send(Message::NewFile(self.path.clone())).is_err() {
+                        return Err("Failed to send data to file watch client: file truncated".to_string());
+                    }
                 }
             },
             Err(e) => {
@@ -145,14 +149,21 @@ impl FileWatch {
             }
         }
         let mut lines_to_send = vec![];
-        for line in reader.lines() {
-            match line {
-                Ok(s) => {
-                    self.read_offset += s.len() as u64;
-                    if self.file_size > self.read_offset {
-                        self.read_offset += 1;
+        let mut offsets = vec![];
+        let mut buffer = vec![];
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => break,
+                Ok(len) => {
+                    offsets.push(self.read_offset);
+                    self.read_offset += len as u64;
+                    let line = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+                    let line = line.strip_suffix(b"\r").unwrap_or(line);
+                    match String::from_utf8(line.to_vec()) {
+                        Ok(s) => lines_to_send.push(s),
+                        Err(_) => return Err("Failed to read line.".to_string()),
                     }
-                    lines_to_send.push(s);
                 },
                 Err(_) => {
                     return Err("Failed to read line.".to_string());
@@ -162,7 +173,8 @@ impl FileWatch {
 
         if !lines_to_send.is_empty() {
             self.watch_timeout = ACTIVE_WATCH_TIMEOUT_MS;
-            if self.message_tx.send(Message::NewLines(lines_to_send)).is_err() {
+            if self.message_tx.send(Message::LineOffsets(offsets)).is_err()
+                || self.message_tx.send(Message::NewLines(lines_to_send)).is_err() {
                 self.should_join = true;
                 return Err("Failed to send data to file watch client: new lines".to_string());
             }
@@ -183,6 +195,7 @@ mod test {
     use std::thread::JoinHandle;
     use std::time::Duration;
     use crate::{FileWatch, Message};
+    use crate::testutil::create_test_file;
 
     const TEST_DIR: &str = "./test/filewatch";
     const WAIT_TIMEOUT_MS: u64 = 1000;
@@ -194,29 +207,8 @@ mod test {
         handle: JoinHandle<()>,
     }
 
-    fn create_test_file(filename: &str, contents: Option<&Vec<String>>) -> PathBuf {
-        let mut file_path = PathBuf::from(TEST_DIR);
-        assert_eq!(std::fs::create_dir_all(&file_path).is_ok(), true);
-        file_path.push(filename);
-
-        if std::fs::remove_file(&file_path).is_err() {
-            log::error!("Failed to remove test file.");
-        }
-
-        let mut file = File::create(file_path.as_path()).unwrap();
-
-        if let Some(strings) = contents {
-            for s in strings {
-                assert_eq!(file.write(s.as_bytes()).is_ok(), true);
-                assert_eq!(file.write(String::from('\n').as_bytes()).is_ok(), true);
-            }
-        }
-
-        file_path
-    }
-
     fn create_context(file_name: &str, file_contents: Option<&Vec<String>>) -> Context {
-        let test_file = create_test_file(file_name, file_contents);
+        let test_file = create_test_file(TEST_DIR, file_name, file_contents.map_or(&[], Vec::as_slice));
         let (tx, rx) = channel();
         let mut file_watch = FileWatch::new(test_file.as_path(), tx);
         let watcher_tx = file_watch.get_tx();
@@ -232,19 +224,44 @@ mod test {
         }
     }
 
+    /// Next lines received, skipping other messages
+    fn next_lines(rx: &Receiver<Message>) -> Option<Vec<String>> {
+        loop {
+            match rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS)) {
+                Ok(Message::NewLines(lines)) => return Some(lines),
+                Ok(_) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+
     #[test]
     fn initial_read() {
         let contents = vec![String::from("Line1"), String::from("Line2")];
         let context = create_context("initial_read.txt", Some(&contents));
 
-        let initial_read = context.rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS));
-        assert_eq!(initial_read.is_ok(), true);
+        let initial_read = next_lines(&context.rx);
+        assert_eq!(initial_read, Some(contents));
+    }
 
-        if let Message::NewLines(read_contents) = initial_read.unwrap() {
-            for line in 0..read_contents.len() {
-                assert_eq!(read_contents[line], contents[line]);
-            }
-        }
+    #[test]
+    fn line_offsets() {
+        let mut file_path = PathBuf::from(TEST_DIR);
+        assert_eq!(std::fs::create_dir_all(&file_path).is_ok(), true);
+        file_path.push("line_offsets.txt");
+        assert_eq!(std::fs::write(&file_path, "Line1\r\nLine2\nLine3").is_ok(), true);
+        let (tx, rx) = channel();
+        let mut file_watch = FileWatch::new(file_path.as_path(), tx);
+        let watcher_tx = file_watch.get_tx();
+        let handle = std::thread::spawn(move || {
+            assert_eq!(file_watch.watch().is_ok(), true);
+        });
+
+        let offsets = rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS));
+        assert!(matches!(offsets, Ok(Message::LineOffsets(offsets)) if offsets == vec![0, 7, 13]));
+        assert_eq!(next_lines(&rx), Some(vec![String::from("Line1"), String::from("Line2"), String::from("Line3")]));
+        assert_eq!(watcher_tx.send(true).is_ok(), true);
+        assert_eq!(handle.join().is_ok(), true);
     }
 
     #[test]
@@ -252,7 +269,7 @@ mod test {
         let contents = vec![String::from("Line1"), String::from("Line2")];
         let context = create_context("new_content.txt", Some(&contents));
 
-        let _initial_read = context.rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS));
+        let _initial_read = next_lines(&context.rx);
         let file = OpenOptions::new().write(true).append(true).open(context.test_file);
         assert_eq!(file.is_ok(), true);
         let mut file = file.unwrap();
@@ -260,11 +277,8 @@ mod test {
         assert_eq!(file.write(new_content.as_bytes()).is_ok(), true);
         assert_eq!(file.flush().is_ok(), true);
 
-        let recv_contents = context.rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS));
-        assert_eq!(recv_contents.is_ok(), true);
-        if let Message::NewLines(msgs) = recv_contents.unwrap() {
-            assert_eq!(msgs[0], new_content);
-        }
+        let recv_contents = next_lines(&context.rx);
+        assert_eq!(recv_contents, Some(vec![new_content]));
     }
 
     #[test]
@@ -272,18 +286,17 @@ mod test {
         let contents = vec![String::from("Line1"), String::from("Line2")];
         let context = create_context("removed_content.txt", Some(&contents));
 
-        let _initial_read = context.rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS));
+        let _initial_read = next_lines(&context.rx);
         let file = File::create(context.test_file);
         assert_eq!(file.is_ok(), true);
         let mut file = file.unwrap();
         let new_content = String::from("New content");
         assert_eq!(file.write(new_content.as_bytes()).is_ok(), true);
 
-        let recv_contents = context.rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS));
-        assert_eq!(recv_contents.is_ok(), true);
-        if let Message::NewLines(msgs) = recv_contents.unwrap() {
-            assert_eq!(msgs[0], new_content);
-        }
+        let new_file = context.rx.recv_timeout(Duration::from_millis(WAIT_TIMEOUT_MS));
+        assert!(matches!(new_file, Ok(Message::NewFile(_))));
+        let recv_contents = next_lines(&context.rx);
+        assert_eq!(recv_contents, Some(vec![new_content]));
     }
 
     #[test]
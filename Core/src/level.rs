@@ -0,0 +1,279 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::str::FromStr;
+use crate::Record;
+
+/// Severity of log record, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 6] = [LogLevel::Trace, LogLevel::Debug, LogLevel::Info, LogLevel::Warning, LogLevel::Error, LogLevel::Fatal];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warning => "WARN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Fatal => "FATAL",
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    /// Parse level name, e.g. `warn` or `WARNING`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(LogLevel::Trace),
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" | "warning" => Ok(LogLevel::Warning),
+            "error" => Ok(LogLevel::Error),
+            "fatal" => Ok(LogLevel::Fatal),
+            _ => Err(format!("Unknown log level {}", s)),
+        }
+    }
+}
+
+/// Level words recognised out of the box, compared ignoring case
+const DEFAULT_WORDS: [(&str, LogLevel); 16] = [
+    ("trace", LogLevel::Trace), ("trc", LogLevel::Trace),
+    ("debug", LogLevel::Debug), ("dbg", LogLevel::Debug),
+    ("info", LogLevel::Info), ("inf", LogLevel::Info),
+    ("warn", LogLevel::Warning), ("warning", LogLevel::Warning), ("wrn", LogLevel::Warning),
+    ("error", LogLevel::Error),
+    ("fatal", LogLevel::Fatal), ("ftl", LogLevel::Fatal), ("crit", LogLevel::Fatal),
+    ("emerg", LogLevel::Fatal), ("emergency", LogLevel::Fatal), ("dpanic", LogLevel::Fatal),
+];
+
+/// Level words common in plain text too, recognised in lines only when shaped like a level,
+/// see `is_level_shaped`
+const DEFAULT_BROAD_WORDS: [(&str, LogLevel); 8] = [
+    ("verbose", LogLevel::Trace), ("information", LogLevel::Info), ("notice", LogLevel::Info),
+    ("err", LogLevel::Error), ("severe", LogLevel::Error),
+    ("critical", LogLevel::Fatal), ("alert", LogLevel::Fatal), ("panic", LogLevel::Fatal),
+];
+
+/// Numeric levels of bunyan and pino
+const DEFAULT_NUMBERS: [(i64, LogLevel); 6] = [
+    (10, LogLevel::Trace), (20, LogLevel::Debug), (30, LogLevel::Info),
+    (40, LogLevel::Warning), (50, LogLevel::Error), (60, LogLevel::Fatal),
+];
+
+/// Tells level of log lines and parsed records
+///
+/// Lines are searched for whole words only, so `STDERR` or `ERROR_COUNT=0` do not make
+/// a line an error. Words common in plain text, e.g. `alert` or `panic`, count in lines
+/// only when shaped like a level, so `alert dialog closed` has no level. Numeric levels
+/// are only recognised in record level fields, a number between known levels counts
+/// as the lower one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelClassifier {
+    words: Vec<(String, LogLevel)>,
+    broad_words: Vec<(String, LogLevel)>,
+    numbers: Vec<(i64, LogLevel)>,
+}
+
+impl Default for LevelClassifier {
+    fn default() -> Self {
+        let words = |words: &[(&str, LogLevel)]| words.iter().map(|(word, level)| (word.to_string(), *level)).collect();
+        Self {
+            words: words(&DEFAULT_WORDS),
+            broad_words: words(&DEFAULT_BROAD_WORDS),
+            numbers: DEFAULT_NUMBERS.to_vec(),
+        }
+    }
+}
+
+/// Whether the word at the byte range of the line looks like a level rather than plain text:
+/// in upper case, e.g. `ALERT`, in brackets, e.g. `[notice]`, or a value, e.g. `level=notice`
+/// or syslog `user.err`
+fn is_level_shaped(line: &str, range: Range<usize>) -> bool {
+    let word = &line[range.clone()];
+    let before = line[..range.start].chars().next_back();
+    let after = line[range.end..].chars().next();
+    let is_upper_case = word.chars().any(char::is_alphabetic) && !word.chars().any(char::is_lowercase);
+    is_upper_case
+        || matches!((before, after), (Some('['), Some(']')) | (Some('<'), Some('>')) | (Some('('), Some(')')))
+        || matches!(before, Some('=') | Some('.'))
+}
+
+impl LevelClassifier {
+    /// Classifier without any known levels
+    pub fn empty() -> Self {
+        Self {
+            words: vec![],
+            broad_words: vec![],
+            numbers: vec![],
+        }
+    }
+
+    /// Recognise word or number as given level, e.g. `CRIT` or `35`
+    pub fn with_level(mut self, word: &str, level: LogLevel) -> Self {
+        match word.parse::<i64>() {
+            Ok(number) => {
+                self.numbers.retain(|(known, _)| *known != number);
+                self.numbers.push((number, level));
+                self.numbers.sort();
+            },
+            Err(_) => {
+                self.words.retain(|(known, _)| !known.eq_ignore_ascii_case(word));
+                self.broad_words.retain(|(known, _)| !known.eq_ignore_ascii_case(word));
+                self.words.push((word.to_ascii_lowercase(), level));
+            }
+        }
+        self
+    }
+
+    /// Add levels written as `word=level` pairs separated by commas or spaces,
+    /// e.g. `CRIT=fatal, 35=warn`
+    pub fn with_levels(mut self, spec: &str) -> Result<Self, String> {
+        for pair in spec.split([',', ' ']).filter(|pair| !pair.is_empty()) {
+            let (word, level) = pair.split_once('=')
+                .ok_or_else(|| format!("Expected word=level, got {}", pair))?;
+            self = self.with_level(word, level.parse()?);
+        }
+        Ok(self)
+    }
+
+    /// Level of value naming it, e.g. record level field `warn` or `40`
+    pub fn level_of(&self, value: &str) -> Option<LogLevel> {
+        let value = value.trim();
+        if let Ok(number) = value.parse::<i64>() {
+            return self.numbers.iter()
+                .rev()
+                .find(|(known, _)| *known <= number)
+                .map(|(_, level)| *level);
+        }
+
+        self.word_level(value)
+    }
+
+    /// Level of the first level word found in the line
+    pub fn classify(&self, line: &str) -> Option<LogLevel> {
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let mut word_start = None;
+        for (idx, c) in line.char_indices().chain([(line.len(), ' ')]) {
+            match word_start {
+                None if is_word_char(c) => word_start = Some(idx),
+                Some(start) if !is_word_char(c) => {
+                    word_start = None;
+                    let word = &line[start..idx];
+                    let level = find_word(&self.words, word).or_else(|| {
+                        find_word(&self.broad_words, word).filter(|_| is_level_shaped(line, start..idx))
+                    });
+                    if level.is_some() {
+                        return level;
+                    }
+                },
+                _ => (),
+            }
+        }
+
+        None
+    }
+
+    /// Level of parsed record, from its level field if present, otherwise from its message
+    pub fn record_level(&self, record: &Record) -> Option<LogLevel> {
+        match &record.level {
+            Some(level) => self.level_of(level),
+            None => self.classify(&record.message),
+        }
+    }
+
+    fn word_level(&self, word: &str) -> Option<LogLevel> {
+        find_word(&self.words, word).or_else(|| find_word(&self.broad_words, word))
+    }
+}
+
+fn find_word(words: &[(String, LogLevel)], word: &str) -> Option<LogLevel> {
+    if word.is_empty() {
+        return None;
+    }
+
+    words.iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(word))
+        .map(|(_, level)| *level)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{LevelClassifier, LogLevel, Record};
+
+    #[test]
+    fn classify_words() {
+        let levels = LevelClassifier::default();
+        assert_eq!(levels.classify("12:00:01 [ERROR] Disk full"), Some(LogLevel::Error));
+        assert_eq!(levels.classify("level=warning msg=slow"), Some(LogLevel::Warning));
+        assert_eq!(levels.classify("Oct 11 22:14:15 router auth.crit sshd: Login failed"), Some(LogLevel::Fatal));
+        assert_eq!(levels.classify("Redirected STDERR, ERROR_COUNT=0"), None);
+        assert_eq!(levels.classify("Informational"), None);
+    }
+
+    #[test]
+    fn classify_broad_words() {
+        let levels = LevelClassifier::default();
+        assert_eq!(levels.classify("alert dialog closed"), None);
+        assert_eq!(levels.classify("Entering critical section, notice sent"), None);
+        assert_eq!(levels.classify("ALERT: disk failing"), Some(LogLevel::Fatal));
+        assert_eq!(levels.classify("[notice] Server started"), Some(LogLevel::Info));
+        assert_eq!(levels.classify("host2 user.err app: Timeout"), Some(LogLevel::Error));
+        assert_eq!(levels.classify("severity=severe"), Some(LogLevel::Error));
+        assert_eq!(levels.level_of("Notice"), Some(LogLevel::Info));
+    }
+
+    #[test]
+    fn numeric_levels() {
+        let levels = LevelClassifier::default();
+        assert_eq!(levels.level_of("30"), Some(LogLevel::Info));
+        assert_eq!(levels.level_of("55"), Some(LogLevel::Error));
+        assert_eq!(levels.level_of("5"), None);
+        assert_eq!(levels.classify("Took 50 ms"), None);
+    }
+
+    #[test]
+    fn custom_levels() {
+        let levels = LevelClassifier::empty().with_levels("OOPS=fatal, 35=warn notice=debug").unwrap();
+        assert_eq!(levels.classify("OOPS something broke"), Some(LogLevel::Fatal));
+        assert_eq!(levels.level_of("37"), Some(LogLevel::Warning));
+        assert_eq!(levels.level_of("Notice"), Some(LogLevel::Debug));
+        assert_eq!(levels.classify("ERROR"), None);
+
+        assert!(LevelClassifier::default().with_levels("OOPS").is_err());
+        assert!(LevelClassifier::default().with_levels("OOPS=bad").is_err());
+    }
+
+    #[test]
+    fn level_of_record() {
+        let levels = LevelClassifier::default();
+        let record = Record { level: Some("40".to_string()), message: "Error rate high".to_string(), ..Default::default() };
+        assert_eq!(levels.record_level(&record), Some(LogLevel::Warning));
+
+        let record = Record { message: "Error rate high".to_string(), ..Default::default() };
+        assert_eq!(levels.record_level(&record), Some(LogLevel::Error));
+    }
+
+    #[test]
+    fn parse_names() {
+        assert_eq!("Warning".parse::<LogLevel>(), Ok(LogLevel::Warning));
+        assert_eq!(LogLevel::Error.to_string(), "ERROR");
+        assert!("loud".parse::<LogLevel>().is_err());
+    }
+}
@@ -7,14 +7,38 @@ use std::thread::JoinHandle;
 use crate::filewatch::FileWatch;
 
 use simplelog::*;
-use crate::client::dirwatchclient::DirWatchClient;
-use crate::client::filewatchclient::FileWatchClient;
-use crate::client::WatchClient;
 use crate::dirwatch::DirWatch;
+pub use crate::ansi::{parse_ansi, strip_ansi, AnsiColor, AnsiSpan, AnsiStyle};
+pub use crate::client::LineSource;
+pub use crate::client::dirwatchclient::DirWatchClient;
+pub use crate::client::filewatchclient::FileWatchClient;
+pub use crate::client::commandclient::CommandClient;
+pub use crate::client::pipeclient::PipeClient;
+pub use crate::client::syslogclient::SyslogClient;
+pub use crate::syslog::SyslogMessage;
+pub use crate::level::{LevelClassifier, LogLevel};
+pub use crate::timestamp::{TimestampParser, TimestampTracker};
+pub use crate::query::Query;
+pub use crate::parser::{builtin_parser, builtin_parsers, detect_parser, is_continuation_line, value_to_text, DETECT_SAMPLE_LINES, JsonParser, LogfmtParser, Record, RecordKeys, RecordParser, RecordStart, RegexParser};
+pub use crate::sender::MessageSender;
+#[cfg(feature = "async")]
+pub use crate::stream::MessageStream;
 
+mod ansi;
 mod filewatch;
 mod dirwatch;
 mod client;
+mod level;
+mod linereader;
+mod parser;
+mod query;
+mod sender;
+mod syslog;
+mod timestamp;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(test)]
+mod testutil;
 
 const LOG_FILENAME: &str = "tailor.log";
 const INVALID_CLIENT_ID : i32 = -1;
@@ -29,8 +53,14 @@ static mut C_CALLBACK: Option<CCallback> = None;
 pub enum Message {
     /// New lines in file we are watching
     NewLines(Vec<String>),
+    /// New lines written to error output of the command we are watching
+    NewErrorLines(Vec<String>),
+    /// Byte offsets of lines of the next `NewLines` message in the file we are watching
+    LineOffsets(Vec<u64>),
     /// New file is open for watching
     NewFile(PathBuf),
+    /// Command we are watching exited with given exit code, none if it was terminated by a signal
+    Exited(Option<i32>),
 }
 
 struct ClientInfo {
@@ -65,22 +95,51 @@ impl Tailor {
     }
 
     pub fn watch(&mut self, path: PathBuf, message_tx: Sender<Message>) -> i32 {
-        let (client_tx,client_rx) = channel();
-        let mut client: Box<dyn WatchClient + Send> = if path.is_file() {
-            Box::new(FileWatchClient::new(path, client_rx, message_tx))
-        } else {
-            Box::new(DirWatchClient::new(path, client_rx, message_tx))
-        };
+        self.watch_source(Self::source_for_path(path), message_tx)
+    }
 
+    /// Rust API: Watch any line source, e.g. one implemented by downstream crate
+    pub fn watch_source(&mut self, source: Box<dyn LineSource>, message_tx: Sender<Message>) -> i32 {
         let max_client_id = self.clients.iter().fold(0, |max, (key,_)| if *key > max { *key } else { max }) + 1;
-        let client_info = ClientInfo {
-            tx: client_tx,
-            handle: std::thread::spawn(move | | { client.start(max_client_id); }),
-        };
+        let client_info = Self::start_client(max_client_id, source, message_tx.into());
         self.clients.insert(max_client_id, client_info);
         max_client_id
     }
 
+    /// Rust API: Watch path and receive messages as async stream
+    ///
+    /// Stream is not registered with this instance: watch is stopped when the stream is dropped.
+    #[cfg(feature = "async")]
+    pub fn watch_stream(&self, path: PathBuf) -> MessageStream {
+        self.watch_source_stream(Self::source_for_path(path))
+    }
+
+    /// Rust API: Watch any line source and receive messages as async stream
+    #[cfg(feature = "async")]
+    pub fn watch_source_stream(&self, source: Box<dyn LineSource>) -> MessageStream {
+        let (message_tx, message_rx) = futures::channel::mpsc::unbounded();
+        let client_info = Self::start_client(INVALID_CLIENT_ID, source, message_tx.into());
+        MessageStream::new(message_rx, client_info)
+    }
+
+    fn source_for_path(path: PathBuf) -> Box<dyn LineSource> {
+        if PipeClient::is_fifo(&path) {
+            Box::new(PipeClient::fifo(path))
+        } else if path.is_file() {
+            Box::new(FileWatchClient::new(path))
+        } else {
+            Box::new(DirWatchClient::new(path))
+        }
+    }
+
+    fn start_client(client_id: i32, mut source: Box<dyn LineSource>, message_tx: MessageSender) -> ClientInfo {
+        let (client_tx,client_rx) = channel();
+        ClientInfo {
+            tx: client_tx,
+            handle: std::thread::spawn(move | | { source.start(client_id, client_rx, message_tx); }),
+        }
+    }
+
     pub fn stop(&mut self, client_id: i32) {
         if let Some(client_info) = &self.clients.remove(&client_id)
         {
@@ -0,0 +1,118 @@
+use std::io::{BufRead, BufReader, Read};
+use std::sync::mpsc::Sender;
+
+use crate::Message;
+
+const MAX_BATCH_LINES: usize = 1000;
+
+/// Reads lines from a non-seekable stream such as a pipe and sends them in batches
+///
+/// Lines already available in the read buffer are sent together, so that a burst of output
+/// results in a single message, just like `FileWatch` reading appended file contents.
+pub struct LineReader<R: Read> {
+    reader: BufReader<R>,
+    message_tx: Sender<Message>,
+    to_message: fn(Vec<String>) -> Message,
+}
+
+impl<R: Read> LineReader<R> {
+    pub fn new(reader: R, message_tx: Sender<Message>) -> Self {
+        Self::with_message(reader, message_tx, Message::NewLines)
+    }
+
+    /// Create reader which wraps read lines into specific message, e.g. `Message::NewErrorLines`
+    pub fn with_message(reader: R, message_tx: Sender<Message>, to_message: fn(Vec<String>) -> Message) -> Self {
+        LineReader {
+            reader: BufReader::new(reader),
+            message_tx,
+            to_message,
+        }
+    }
+
+    /// Read until end of stream. Blocks while waiting for more data.
+    pub fn read(&mut self) -> Result<(), String> {
+        let mut lines_to_send = vec![];
+        let mut buf = vec![];
+        loop {
+            buf.clear();
+            let read = match self.reader.read_until(b'\n', &mut buf) {
+                Ok(read) => read,
+                Err(error) => {
+                    self.send(&mut lines_to_send)?;
+                    return Err(format!("Failed to read line: {}", error));
+                }
+            };
+
+            if read == 0 {
+                return self.send(&mut lines_to_send);
+            }
+
+            if buf.ends_with(b"\n") {
+                buf.pop();
+                if buf.ends_with(b"\r") {
+                    buf.pop();
+                }
+            }
+            lines_to_send.push(String::from_utf8_lossy(&buf).into_owned());
+
+            if self.reader.buffer().is_empty() || lines_to_send.len() >= MAX_BATCH_LINES {
+                self.send(&mut lines_to_send)?;
+            }
+        }
+    }
+
+    fn send(&self, lines: &mut Vec<String>) -> Result<(), String> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        if self.message_tx.send((self.to_message)(std::mem::take(lines))).is_err() {
+            return Err("Failed to send data to line reader owner: new lines".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use std::sync::mpsc::channel;
+    use crate::linereader::LineReader;
+    use crate::Message;
+
+    #[test]
+    fn read_lines() {
+        let (tx, rx) = channel();
+        let mut reader = LineReader::new(Cursor::new("Line1\nLine2\r\nLine3"), tx);
+        assert!(reader.read().is_ok());
+
+        let mut received = vec![];
+        while let Ok(Message::NewLines(lines)) = rx.try_recv() {
+            received.extend(lines);
+        }
+        assert_eq!(received, vec!["Line1", "Line2", "Line3"]);
+    }
+
+    #[test]
+    fn batch_lines() {
+        let (tx, rx) = channel();
+        let mut reader = LineReader::new(Cursor::new("Line1\nLine2\nLine3\n"), tx);
+        assert!(reader.read().is_ok());
+
+        match rx.try_recv() {
+            Ok(Message::NewLines(lines)) => assert_eq!(lines.len(), 3),
+            _ => panic!("Expected single batch of lines"),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn custom_message() {
+        let (tx, rx) = channel();
+        let mut reader = LineReader::with_message(Cursor::new("Error"), tx, Message::NewErrorLines);
+        assert!(reader.read().is_ok());
+
+        assert!(matches!(rx.try_recv(), Ok(Message::NewErrorLines(lines)) if lines == vec!["Error"]));
+    }
+}
@@ -0,0 +1,103 @@
+use regex::Regex;
+use crate::parser::RecordParser;
+
+/// Starts of unindented lines which still continue the record above, e.g. in stack traces
+const CONTINUATION_PREFIXES: [&str; 4] = [
+    "Caused by:",
+    "Traceback (most recent call last):",
+    "During handling of the above exception",
+    "The above exception was the direct cause",
+];
+/// Endings of exception class names starting lines of stack traces
+const EXCEPTION_SUFFIXES: [&str; 3] = ["Exception", "Error", "Throwable"];
+
+/// Tells which lines start a new record, other lines belong to the record above them,
+/// e.g. lines of a stack trace following the log line which reported the exception.
+pub enum RecordStart {
+    /// Lines matching the pattern start records
+    Pattern(Regex),
+    /// Lines understood by the parser start records
+    Format(Box<dyn RecordParser>),
+    /// Indented lines and known stack trace lines continue records, see `is_continuation_line`
+    Heuristic,
+}
+
+impl RecordStart {
+    pub fn pattern(pattern: &str) -> Result<Self, String> {
+        match Regex::new(pattern) {
+            Ok(regex) => Ok(RecordStart::Pattern(regex)),
+            Err(error) => Err(format!("Invalid record start pattern {}: {}", pattern, error)),
+        }
+    }
+
+    pub fn is_start(&self, line: &str) -> bool {
+        match self {
+            RecordStart::Pattern(regex) => regex.is_match(line),
+            RecordStart::Format(parser) => parser.parse(line).is_some(),
+            RecordStart::Heuristic => !is_continuation_line(line),
+        }
+    }
+}
+
+/// Whether line looks like a continuation of the line above: indented, e.g.
+/// `\tat com.example.App.main`, starting with exception name, e.g.
+/// `java.lang.IllegalStateException: Not connected`, or starting a nested part of Java
+/// or Python stack trace
+pub fn is_continuation_line(line: &str) -> bool {
+    if line.trim().is_empty() {
+        return false;
+    }
+
+    line.starts_with([' ', '\t'])
+        || CONTINUATION_PREFIXES.iter().any(|prefix| line.starts_with(prefix))
+        || is_exception_line(line)
+}
+
+/// Whether line starts with name of exception class, alone or followed by colon,
+/// e.g. `java.io.IOException: Timeout` or Python's `ValueError: bad value`
+fn is_exception_line(line: &str) -> bool {
+    let name = line.split_once(':').map_or(line.trim_end(), |(name, _)| name);
+    let is_name = name.chars().all(|c| c.is_alphanumeric() || matches!(c, '.' | '_' | '$'));
+    is_name && EXCEPTION_SUFFIXES.iter().any(|suffix| name.len() > suffix.len() && name.ends_with(suffix))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::{builtin_parser, RecordStart};
+
+    const JAVA_TRACE: [&str; 6] = [
+        "2023-10-11 22:14:15,003 ERROR [main] com.example.App - Failed",
+        "java.lang.IllegalStateException: Not connected",
+        "\tat com.example.App.main(App.java:10)",
+        "Caused by: java.io.IOException: Timeout",
+        "\t... 1 more",
+        "2023-10-11 22:14:16,001 INFO [main] com.example.App - Retrying",
+    ];
+
+    fn starts(record_start: &RecordStart) -> Vec<bool> {
+        JAVA_TRACE.iter().map(|line| record_start.is_start(line)).collect()
+    }
+
+    #[test]
+    fn heuristic() {
+        assert_eq!(starts(&RecordStart::Heuristic), vec![true, false, false, false, false, true]);
+        assert!(RecordStart::Heuristic.is_start(""));
+        assert!(!RecordStart::Heuristic.is_start("ValueError: bad value"));
+        assert!(!RecordStart::Heuristic.is_start("com.example.NotFoundError"));
+        assert!(RecordStart::Heuristic.is_start("Error: disk full"));
+        assert!(RecordStart::Heuristic.is_start("Exception handler installed: retries=3"));
+    }
+
+    #[test]
+    fn pattern() {
+        let record_start = RecordStart::pattern(r"^\d{4}-\d{2}-\d{2} ").unwrap();
+        assert_eq!(starts(&record_start), vec![true, false, false, false, false, true]);
+        assert!(RecordStart::pattern("(").is_err());
+    }
+
+    #[test]
+    fn format() {
+        let record_start = RecordStart::Format(builtin_parser("log4j").unwrap());
+        assert_eq!(starts(&record_start), vec![true, false, false, false, false, true]);
+    }
+}
@@ -0,0 +1,140 @@
+use serde_json::{Map, Value};
+use crate::parser::{value_to_text, Record, RecordKeys, RecordParser};
+
+/// Parses JSON Lines logs, one JSON object per line
+///
+/// Nested keys are addressed with dots, e.g. `log.level`. Keys not used for timestamp,
+/// level, target or message are kept as record fields with their JSON values.
+#[derive(Default)]
+pub struct JsonParser {
+    keys: RecordKeys,
+}
+
+impl JsonParser {
+    pub fn new(keys: RecordKeys) -> Self {
+        JsonParser {
+            keys,
+        }
+    }
+
+    /// Find value of first present key. Returns it along with the top level key and,
+    /// for nested keys, the child key it was found under.
+    fn find<'a>(object: &'a Map<String, Value>, keys: &[String]) -> Option<(&'a Value, String, Option<String>)> {
+        for key in keys {
+            if let Some(value) = object.get(key) {
+                return Some((value, key.clone(), None));
+            }
+
+            if let Some((parent, child)) = key.split_once('.') {
+                if let Some(Value::Object(nested)) = object.get(parent) {
+                    if let Some(value) = nested.get(child) {
+                        return Some((value, parent.to_string(), Some(child.to_string())));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl RecordParser for JsonParser {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn parse(&self, line: &str) -> Option<Record> {
+        if !line.trim_start().starts_with('{') {
+            return None;
+        }
+
+        let object = match serde_json::from_str(line) {
+            Ok(Value::Object(object)) => object,
+            _ => return None,
+        };
+
+        let mut used_keys = vec![];
+        let mut extract = |keys: &[String]| {
+            Self::find(&object, keys).map(|(value, key, child)| {
+                used_keys.push((key, child));
+                value_to_text(value)
+            })
+        };
+        let timestamp = extract(&self.keys.timestamp);
+        let level = extract(&self.keys.level);
+        let target = extract(&self.keys.target);
+        let message = extract(&self.keys.message);
+
+        let fields = object.into_iter().filter_map(|(key, value)| {
+            let used_children: Vec<&String> = used_keys.iter()
+                .filter(|(used_key, _)| *used_key == key)
+                .filter_map(|(_, child)| child.as_ref())
+                .collect();
+            if used_keys.iter().any(|(used_key, child)| *used_key == key && child.is_none()) {
+                return None;
+            }
+
+            match value {
+                Value::Object(mut nested) if !used_children.is_empty() => {
+                    nested.retain(|child, _| !used_children.contains(&child));
+                    (!nested.is_empty()).then_some((key, Value::Object(nested)))
+                },
+                value => Some((key, value)),
+            }
+        }).collect();
+
+        Some(Record {
+            timestamp,
+            level,
+            target,
+            message: message.unwrap_or_default(),
+            fields,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::parser::{JsonParser, RecordKeys, RecordParser};
+
+    #[test]
+    fn parse_default_keys() {
+        let parser = JsonParser::default();
+        let record = parser.parse(r#"{"ts":"2023-10-11T22:14:15Z","level":30,"logger":"api","msg":"Request done","latency_ms":42,"user":{"id":7}}"#).unwrap();
+
+        assert_eq!(record.timestamp.as_deref(), Some("2023-10-11T22:14:15Z"));
+        assert_eq!(record.level.as_deref(), Some("30"));
+        assert_eq!(record.target.as_deref(), Some("api"));
+        assert_eq!(record.message, "Request done");
+        assert_eq!(record.field("latency_ms"), Some(&json!(42)));
+        assert_eq!(record.field("user"), Some(&json!({"id": 7})));
+        assert_eq!(record.fields.len(), 2);
+    }
+
+    #[test]
+    fn parse_custom_and_nested_keys() {
+        let keys = RecordKeys {
+            timestamp: vec!["when".to_string()],
+            level: vec!["log.level".to_string()],
+            target: vec![],
+            message: vec!["event".to_string()],
+        };
+        let parser = JsonParser::new(keys);
+        let record = parser.parse(r#"{"when":"12:00","log":{"level":"warn"},"event":"Disk full","msg":"kept"}"#).unwrap();
+
+        assert_eq!(record.timestamp.as_deref(), Some("12:00"));
+        assert_eq!(record.level.as_deref(), Some("warn"));
+        assert_eq!(record.message, "Disk full");
+        assert_eq!(record.field_str("msg"), Some("kept"));
+        assert!(record.field("log").is_none());
+    }
+
+    #[test]
+    fn reject_non_objects() {
+        let parser = JsonParser::default();
+        assert!(parser.parse("plain text").is_none());
+        assert!(parser.parse("{broken").is_none());
+        assert!(parser.parse("[1, 2]").is_none());
+    }
+}
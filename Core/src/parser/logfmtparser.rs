@@ -0,0 +1,163 @@
+use serde_json::Value;
+use crate::parser::{Record, RecordKeys, RecordParser};
+
+/// Split logfmt line into key and value pairs. Keys without `=` have no value.
+///
+/// Values may be double quoted, quoted values support `\"`, `\\`, `\n`, `\r` and `\t`
+/// escapes. Returns none for unterminated quotes or quotes in keys.
+fn split_pairs(line: &str) -> Option<Vec<(String, Option<String>)>> {
+    let mut pairs = vec![];
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+            if c == '"' {
+                return None;
+            }
+            key.push(c);
+        }
+        if key.is_empty() {
+            return None;
+        }
+
+        if chars.next_if_eq(&'=').is_none() {
+            pairs.push((key, None));
+            continue;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        'n' => value.push('\n'),
+                        'r' => value.push('\r'),
+                        't' => value.push('\t'),
+                        c => value.push(c),
+                    },
+                    c => value.push(c),
+                }
+            }
+            if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                return None;
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+        pairs.push((key, Some(value)));
+    }
+
+    Some(pairs)
+}
+
+/// Parses logfmt logs, e.g. `ts=2023-10-11T22:14:15Z level=warn msg="Disk full" user=42`
+///
+/// Lines are accepted when most of their words are `key=value` pairs. Keys not used for
+/// timestamp, level, target or message are kept as string fields, keys without value
+/// as `true`.
+#[derive(Default)]
+pub struct LogfmtParser {
+    keys: RecordKeys,
+}
+
+impl LogfmtParser {
+    pub fn new(keys: RecordKeys) -> Self {
+        LogfmtParser {
+            keys,
+        }
+    }
+}
+
+impl RecordParser for LogfmtParser {
+    fn name(&self) -> &str {
+        "logfmt"
+    }
+
+    fn parse(&self, line: &str) -> Option<Record> {
+        let mut pairs = split_pairs(line)?;
+        let with_value = pairs.iter().filter(|(_, value)| value.is_some()).count();
+        if with_value * 2 <= pairs.len() {
+            return None;
+        }
+
+        let mut extract = |keys: &[String]| {
+            let idx = keys.iter().find_map(|key| {
+                pairs.iter().position(|(name, value)| name == key && value.is_some())
+            })?;
+            pairs.remove(idx).1
+        };
+        let timestamp = extract(&self.keys.timestamp);
+        let level = extract(&self.keys.level);
+        let target = extract(&self.keys.target);
+        let message = extract(&self.keys.message);
+
+        let fields = pairs.into_iter().map(|(key, value)| {
+            let value = match value {
+                Some(value) => Value::String(value),
+                None => Value::Bool(true),
+            };
+            (key, value)
+        }).collect();
+
+        Some(Record {
+            timestamp,
+            level,
+            target,
+            message: message.unwrap_or_default(),
+            fields,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::parser::{LogfmtParser, RecordParser};
+    use super::split_pairs;
+
+    #[test]
+    fn quoting_and_escaping() {
+        let pairs = split_pairs(r#"a=1 b="two words" c="say \"hi\"\n" d= flag"#).unwrap();
+        assert_eq!(pairs, vec![
+            ("a".to_string(), Some("1".to_string())),
+            ("b".to_string(), Some("two words".to_string())),
+            ("c".to_string(), Some("say \"hi\"\n".to_string())),
+            ("d".to_string(), Some("".to_string())),
+            ("flag".to_string(), None),
+        ]);
+
+        assert!(split_pairs(r#"a="unterminated"#).is_none());
+        assert!(split_pairs(r#"a="x"y"#).is_none());
+        assert!(split_pairs(r#""a"=1"#).is_none());
+    }
+
+    #[test]
+    fn parse_record() {
+        let parser = LogfmtParser::default();
+        let record = parser.parse(r#"ts=2023-10-11T22:14:15Z level=warn msg="Disk almost full" user=42 retry"#).unwrap();
+
+        assert_eq!(record.timestamp.as_deref(), Some("2023-10-11T22:14:15Z"));
+        assert_eq!(record.level.as_deref(), Some("warn"));
+        assert_eq!(record.target, None);
+        assert_eq!(record.message, "Disk almost full");
+        assert_eq!(record.field_str("user"), Some("42"));
+        assert_eq!(record.field("retry"), Some(&json!(true)));
+        assert_eq!(record.fields.len(), 2);
+    }
+
+    #[test]
+    fn reject_plain_text() {
+        let parser = LogfmtParser::default();
+        assert!(parser.parse("Oct 11 22:14:15 router app: user=42").is_none());
+        assert!(parser.parse("just some text").is_none());
+        assert!(parser.parse("").is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use serde_json::Value;
+
+pub mod regexparser;
+pub mod jsonparser;
+pub mod logfmtparser;
+pub mod grouping;
+
+pub use regexparser::RegexParser;
+pub use jsonparser::JsonParser;
+pub use logfmtparser::LogfmtParser;
+pub use grouping::{is_continuation_line, RecordStart};
+
+/// How many lines from the beginning of the log are used to detect its format
+pub const DETECT_SAMPLE_LINES: usize = 20;
+
+/// (name, pattern) of formats recognised out of the box, most specific first
+const BUILTIN_FORMATS: [(&str, &str); 8] = [
+    ("syslog", r"^(?P<timestamp>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}|\d{4}-\d{2}-\d{2}T\S+|-) (?P<host>\S+) (?:(?P<facility>[a-z0-9-]+)\.(?P<level>[a-z]+) )?(?P<target>[^:\[\s]+)(?:\[(?P<pid>\d+)\])?: (?P<message>.*)$"),
+    ("combined", r#"^(?P<remote_addr>\S+) \S+ (?P<remote_user>\S+) \[(?P<timestamp>[^\]]+)\] "(?P<message>[^"]*)" (?P<status>\d{3}) (?P<bytes>\d+|-)(?: "(?P<referer>[^"]*)" "(?P<user_agent>[^"]*)")?"#),
+    ("python", r"^(?P<timestamp>\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2},\d{3}) - (?P<target>\S+) - (?P<level>[A-Z]+) - (?P<message>.*)$"),
+    ("python_basic", r"^(?P<level>DEBUG|INFO|WARNING|ERROR|CRITICAL):(?P<target>[^:]*):(?P<message>.*)$"),
+    ("env_logger", r"^\[(?P<timestamp>\d{4}-\d{2}-\d{2}T\S+)\s+(?P<level>[A-Z]+)\s+(?P<target>[^\]\s]+)\]\s?(?P<message>.*)$"),
+    ("logback", r"^(?P<timestamp>\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?)\s+\[(?P<thread>[^\]]*)\]\s+(?P<level>[A-Za-z]+)\s+(?P<target>\S+)\s+-\s+(?P<message>.*)$"),
+    ("log4j", r"^(?P<timestamp>\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?)\s+(?P<level>[A-Za-z]+)\s+(?:\[(?P<thread>[^\]]*)\]\s+)?(?P<target>\S+)\s+-\s+(?P<message>.*)$"),
+    ("simplelog", r"^(?P<timestamp>\d{2}:\d{2}:\d{2})\s+\[(?P<level>[A-Za-z]+)\]\s+(?P<message>.*)$"),
+];
+
+/// Keys looked up in structured lines for each record part, first present key wins
+#[derive(Clone)]
+pub struct RecordKeys {
+    pub timestamp: Vec<String>,
+    pub level: Vec<String>,
+    pub target: Vec<String>,
+    pub message: Vec<String>,
+}
+
+impl Default for RecordKeys {
+    fn default() -> Self {
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect();
+        Self {
+            timestamp: keys(&["timestamp", "time", "ts", "@timestamp", "date"]),
+            level: keys(&["level", "severity", "lvl", "log.level", "levelname"]),
+            target: keys(&["logger", "target", "logger_name", "name", "module"]),
+            message: keys(&["message", "msg", "@message", "text"]),
+        }
+    }
+}
+
+/// Log line split into its parts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record {
+    pub timestamp: Option<String>,
+    pub level: Option<String>,
+    /// Logger name or target module which produced the record
+    pub target: Option<String>,
+    pub message: String,
+    /// Any other named parts of the line, in order of appearance. Values of structured
+    /// formats such as JSON keep their type, parts of plain text lines are strings.
+    pub fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Value of string field
+    pub fn field_str(&self, name: &str) -> Option<&str> {
+        self.field(name).and_then(|value| value.as_str())
+    }
+
+    /// Field value as displayed to user: strings without quotes, anything else as JSON
+    pub fn field_text(&self, name: &str) -> Option<String> {
+        self.field(name).map(value_to_text)
+    }
+
+    /// Text of record part or field with given name. `timestamp`, `level`, `target`
+    /// and `message` refer to the record parts, anything else to fields.
+    pub fn get_text(&self, name: &str) -> Option<String> {
+        match name {
+            "timestamp" => self.timestamp.clone(),
+            "level" => self.level.clone(),
+            "target" => self.target.clone(),
+            "message" => Some(self.message.clone()),
+            _ => self.field_text(name),
+        }
+    }
+}
+
+pub fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+/// Turns log lines into records. Returns none for lines not matching the format,
+/// e.g. continuation lines of a stack trace.
+pub trait RecordParser: Send + Sync {
+    fn name(&self) -> &str;
+    fn parse(&self, line: &str) -> Option<Record>;
+}
+
+/// Parsers for all formats supported out of the box
+pub fn builtin_parsers() -> Vec<Box<dyn RecordParser>> {
+    let mut parsers: Vec<Box<dyn RecordParser>> = vec![Box::new(JsonParser::default())];
+    parsers.extend(BUILTIN_FORMATS.iter()
+        .filter_map(|(name, pattern)| RegexParser::new(name, pattern).ok())
+        .map(|parser| Box::new(parser) as Box<dyn RecordParser>));
+    // Last, so that formats with key=value pairs in the message win
+    parsers.push(Box::new(LogfmtParser::default()));
+    parsers
+}
+
+/// Built-in parser for format with given name
+pub fn builtin_parser(name: &str) -> Option<Box<dyn RecordParser>> {
+    builtin_parsers().into_iter().find(|parser| parser.name() == name)
+}
+
+/// Pick built-in parser which understands most of the first lines of the log
+///
+/// Returns none if no parser matches at least half of non-empty sample lines.
+pub fn detect_parser(lines: &[String]) -> Option<Box<dyn RecordParser>> {
+    let sample: Vec<&String> = lines.iter()
+        .filter(|line| !line.trim().is_empty())
+        .take(DETECT_SAMPLE_LINES)
+        .collect();
+    if sample.is_empty() {
+        return None;
+    }
+
+    let mut best = None;
+    let mut best_matched = 0;
+    for parser in builtin_parsers() {
+        let matched = sample.iter().filter(|line| parser.parse(line).is_some()).count();
+        if matched * 2 >= sample.len() && matched > best_matched {
+            best = Some(parser);
+            best_matched = matched;
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::{builtin_parser, builtin_parsers, detect_parser};
+
+    fn detect(lines: &[&str]) -> Option<String> {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        detect_parser(&lines).map(|parser| parser.name().to_string())
+    }
+
+    #[test]
+    fn builtin_patterns_compile() {
+        assert_eq!(builtin_parsers().len(), super::BUILTIN_FORMATS.len() + 2);
+    }
+
+    #[test]
+    fn detect_formats() {
+        assert_eq!(detect(&["[2023-10-11T22:14:15Z INFO  my_app::server] Listening", "[2023-10-11T22:14:16Z WARN  my_app::db] Slow query"]).as_deref(), Some("env_logger"));
+        assert_eq!(detect(&["2023-10-11 22:14:15,003 ERROR [main] com.example.App - Failed", "\tat com.example.App.main(App.java:10)", "2023-10-11 22:14:16,001 INFO [main] com.example.App - Retrying"]).as_deref(), Some("log4j"));
+        assert_eq!(detect(&["2023-10-11 22:14:15.003 [main] INFO  com.example.App - Started"]).as_deref(), Some("logback"));
+        assert_eq!(detect(&["2023-10-11 22:14:15,003 - worker - WARNING - Retrying"]).as_deref(), Some("python"));
+        assert_eq!(detect(&["WARNING:root:Retrying"]).as_deref(), Some("python_basic"));
+        assert_eq!(detect(&["Oct 11 22:14:15 router sshd[42]: Login failed"]).as_deref(), Some("syslog"));
+        assert_eq!(detect(&[r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /index.html HTTP/1.0" 200 2326 "-" "curl/8.0""#]).as_deref(), Some("combined"));
+        assert_eq!(detect(&["21:09:39 [INFO] Watching file ./test.txt"]).as_deref(), Some("simplelog"));
+        assert_eq!(detect(&[r#"{"time":"2023-10-11T22:14:15Z","level":"info","msg":"Started"}"#]).as_deref(), Some("json"));
+        assert_eq!(detect(&[r#"ts=2023-10-11T22:14:15Z level=warn msg="Disk full" user=42"#]).as_deref(), Some("logfmt"));
+        assert_eq!(detect(&["just some text", "more text"]), None);
+    }
+
+    #[test]
+    fn parse_syslog_line() {
+        let parser = builtin_parser("syslog").unwrap();
+        let record = parser.parse("2003-10-11T22:14:15.003Z switch local4.notice app: Port up").unwrap();
+
+        assert_eq!(record.timestamp.as_deref(), Some("2003-10-11T22:14:15.003Z"));
+        assert_eq!(record.level.as_deref(), Some("notice"));
+        assert_eq!(record.target.as_deref(), Some("app"));
+        assert_eq!(record.field_str("host"), Some("switch"));
+        assert_eq!(record.field_str("facility"), Some("local4"));
+        assert_eq!(record.message, "Port up");
+    }
+
+    #[test]
+    fn parse_log4j_line() {
+        let parser = builtin_parser("log4j").unwrap();
+        let record = parser.parse("2023-10-11 22:14:15,003 ERROR [main] com.example.App - Failed to connect").unwrap();
+
+        assert_eq!(record.timestamp.as_deref(), Some("2023-10-11 22:14:15,003"));
+        assert_eq!(record.level.as_deref(), Some("ERROR"));
+        assert_eq!(record.target.as_deref(), Some("com.example.App"));
+        assert_eq!(record.field_str("thread"), Some("main"));
+        assert_eq!(record.message, "Failed to connect");
+    }
+}
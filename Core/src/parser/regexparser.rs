@@ -0,0 +1,97 @@
+use regex::Regex;
+use serde_json::Value;
+use crate::parser::{Record, RecordParser};
+
+const TIMESTAMP_GROUP: &str = "timestamp";
+const LEVEL_GROUP: &str = "level";
+const TARGET_GROUP: &str = "target";
+const MESSAGE_GROUP: &str = "message";
+
+/// Parses lines with a regular expression
+///
+/// Named capture groups `timestamp`, `level`, `target` and `message` fill the corresponding
+/// record fields, any other named group becomes an extra field. Without `message` group
+/// the whole line is used as the message.
+pub struct RegexParser {
+    name: String,
+    regex: Regex,
+}
+
+impl RegexParser {
+    pub fn new(name: &str, pattern: &str) -> Result<Self, String> {
+        match Regex::new(pattern) {
+            Ok(regex) => Ok(RegexParser {
+                name: name.to_string(),
+                regex,
+            }),
+            Err(error) => Err(format!("Invalid parser pattern {}: {}", pattern, error)),
+        }
+    }
+
+    pub fn get_pattern(&self) -> &str {
+        self.regex.as_str()
+    }
+}
+
+impl RecordParser for RegexParser {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parse(&self, line: &str) -> Option<Record> {
+        let captures = self.regex.captures(line)?;
+        let mut record = Record {
+            message: line.to_string(),
+            ..Default::default()
+        };
+
+        for name in self.regex.capture_names().flatten() {
+            let value = match captures.name(name) {
+                Some(value) => value.as_str().to_string(),
+                None => continue,
+            };
+
+            match name {
+                TIMESTAMP_GROUP => record.timestamp = Some(value),
+                LEVEL_GROUP => record.level = Some(value),
+                TARGET_GROUP => record.target = Some(value),
+                MESSAGE_GROUP => record.message = value,
+                _ => record.fields.push((name.to_string(), Value::String(value))),
+            }
+        }
+
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::{RecordParser, RegexParser};
+
+    #[test]
+    fn named_captures() {
+        let parser = RegexParser::new("custom", r"^(?P<timestamp>\S+) <(?P<level>\w+)> user=(?P<user>\d+) (?P<message>.*)$").unwrap();
+        let record = parser.parse("12:00:01 <warn> user=42 Quota exceeded").unwrap();
+
+        assert_eq!(record.timestamp.as_deref(), Some("12:00:01"));
+        assert_eq!(record.level.as_deref(), Some("warn"));
+        assert_eq!(record.target, None);
+        assert_eq!(record.message, "Quota exceeded");
+        assert_eq!(record.field_str("user"), Some("42"));
+    }
+
+    #[test]
+    fn whole_line_message() {
+        let parser = RegexParser::new("custom", r"^\[(?P<level>\w+)\]").unwrap();
+        let record = parser.parse("[INFO] Started").unwrap();
+
+        assert_eq!(record.level.as_deref(), Some("INFO"));
+        assert_eq!(record.message, "[INFO] Started");
+        assert!(parser.parse("Started").is_none());
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        assert!(RegexParser::new("custom", r"(?P<level>").is_err());
+    }
+}
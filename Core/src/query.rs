@@ -0,0 +1,539 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+use chrono::{DateTime, TimeDelta, Utc};
+use regex::{Regex, RegexBuilder};
+use crate::{LevelClassifier, Record, TimestampParser};
+
+/// Field name meaning time of the line rather than a record field
+const TIME_FIELD: &str = "time";
+const LEVEL_FIELD: &str = "level";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Match,
+    NoMatch,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Match => "~",
+            Op::NoMatch => "!~",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Text(Regex),
+    /// Field name, operator, value and the whole comparison as written
+    Field(String, Op, String, String),
+}
+
+#[derive(Debug)]
+enum Value {
+    Text(String),
+    Regex(Regex),
+    Time(DateTime<Utc>),
+    /// Time this long before the line is matched
+    TimeAgo(TimeDelta),
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Text(Regex),
+    /// Field name, operator, value and the comparison text matched by lines without record
+    Field(String, Op, Value, Regex),
+}
+
+/// Filter for log lines
+///
+/// Words and `"quoted phrases"` match anywhere in the line ignoring case, `/regex/` literals
+/// are case sensitive unless written as `/regex/i`. Terms are combined with `AND` (also
+/// implied between terms), `OR` and `NOT` (also `-term` or `!term`), and grouped with
+/// parentheses. Comparisons like `level>=warn`, `latency_ms>500`, `user=42` or `path~^/api`
+/// apply to parsed records, `time>=2023-10-11T22:00` or `time>-15m` to line time. Numbers
+/// are compared as numbers, levels by severity and other values as case insensitive text.
+/// Comparisons of missing fields never match, lines without record match comparisons
+/// written in them, e.g. `user=42`, as text.
+#[derive(Debug)]
+pub struct Query {
+    expr: Option<Expr>,
+    levels: LevelClassifier,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::parse_with(text, &TimestampParser::default())
+    }
+
+    /// Parse query reading times in comparisons with given parser, e.g. to set their zone
+    pub fn parse_with(text: &str, timestamps: &TimestampParser) -> Result<Self, String> {
+        let tokens = tokenize(text)?;
+        let mut tokens = tokens.into_iter().peekable();
+        let expr = match tokens.peek() {
+            Some(_) => Some(parse_or(&mut tokens, timestamps)?),
+            None => None,
+        };
+        if let Some(token) = tokens.next() {
+            return Err(format!("Unexpected {}", describe(&token)));
+        }
+
+        Ok(Query {
+            expr,
+            levels: LevelClassifier::default(),
+        })
+    }
+
+    /// Tell levels in `level` comparisons with given classifier
+    pub fn with_levels(mut self, levels: LevelClassifier) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    /// Query matching every line
+    pub fn is_empty(&self) -> bool {
+        self.expr.is_none()
+    }
+
+    /// Whether the query compares record fields, so lines need to be parsed
+    pub fn needs_record(&self) -> bool {
+        self.expr.as_ref().is_some_and(|expr| expr.uses_field(&|name| name != TIME_FIELD))
+    }
+
+    /// Whether the query compares line time
+    pub fn needs_time(&self) -> bool {
+        self.expr.as_ref().is_some_and(|expr| expr.uses_field(&|name| name == TIME_FIELD))
+    }
+
+    pub fn matches(&self, line: &str, record: Option<&Record>, time: Option<DateTime<Utc>>) -> bool {
+        match &self.expr {
+            Some(expr) => expr.matches(line, record, time, &self.levels),
+            None => true,
+        }
+    }
+}
+
+impl Expr {
+    fn uses_field(&self, filter: &dyn Fn(&str) -> bool) -> bool {
+        match self {
+            Expr::And(left, right) | Expr::Or(left, right) => left.uses_field(filter) || right.uses_field(filter),
+            Expr::Not(expr) => expr.uses_field(filter),
+            Expr::Text(_) => false,
+            Expr::Field(name, _, _, _) => filter(name),
+        }
+    }
+
+    fn matches(&self, line: &str, record: Option<&Record>, time: Option<DateTime<Utc>>, levels: &LevelClassifier) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(line, record, time, levels) && right.matches(line, record, time, levels),
+            Expr::Or(left, right) => left.matches(line, record, time, levels) || right.matches(line, record, time, levels),
+            Expr::Not(expr) => !expr.matches(line, record, time, levels),
+            Expr::Text(regex) => regex.is_match(line),
+            Expr::Field(name, op, Value::Time(expected), _) if name == TIME_FIELD => {
+                time.is_some_and(|time| compare(*op, time.cmp(expected)))
+            },
+            Expr::Field(name, op, Value::TimeAgo(delta), _) if name == TIME_FIELD => {
+                let expected = Utc::now().checked_sub_signed(*delta);
+                time.zip(expected).is_some_and(|(time, expected)| compare(*op, time.cmp(&expected)))
+            },
+            Expr::Field(name, op, value, text) => {
+                let record = match record {
+                    Some(record) => record,
+                    None => return text.is_match(line),
+                };
+                let actual = match record.get_text(name) {
+                    Some(actual) => actual,
+                    None => return false,
+                };
+
+                match value {
+                    Value::Regex(regex) => regex.is_match(&actual) != (*op == Op::NoMatch),
+                    Value::Text(expected) => compare(*op, compare_values(name, &actual, expected, levels)),
+                    Value::Time(_) | Value::TimeAgo(_) => false,
+                }
+            },
+        }
+    }
+}
+
+fn compare(op: Op, ordering: Ordering) -> bool {
+    match op {
+        Op::Eq | Op::Match => ordering == Ordering::Equal,
+        Op::Ne | Op::NoMatch => ordering != Ordering::Equal,
+        Op::Gt => ordering == Ordering::Greater,
+        Op::Ge => ordering != Ordering::Less,
+        Op::Lt => ordering == Ordering::Less,
+        Op::Le => ordering != Ordering::Greater,
+    }
+}
+
+fn compare_values(name: &str, actual: &str, expected: &str, levels: &LevelClassifier) -> Ordering {
+    if name == LEVEL_FIELD {
+        if let (Some(actual), Some(expected)) = (levels.level_of(actual), levels.level_of(expected)) {
+            return actual.cmp(&expected);
+        }
+    }
+
+    if let (Ok(actual), Ok(expected)) = (actual.trim().parse::<f64>(), expected.parse::<f64>()) {
+        return actual.partial_cmp(&expected).unwrap_or(Ordering::Equal);
+    }
+
+    actual.to_lowercase().cmp(&expected.to_lowercase())
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Open => "(".to_string(),
+        Token::Close => ")".to_string(),
+        Token::And => "AND".to_string(),
+        Token::Or => "OR".to_string(),
+        Token::Not => "NOT".to_string(),
+        Token::Text(regex) => regex.as_str().to_string(),
+        Token::Field(name, _, value, _) => format!("{} comparison with {}", name, value),
+    }
+}
+
+type Tokens = Peekable<std::vec::IntoIter<Token>>;
+
+fn parse_or(tokens: &mut Tokens, timestamps: &TimestampParser) -> Result<Expr, String> {
+    let mut expr = parse_and(tokens, timestamps)?;
+    while let Some(Token::Or) = tokens.peek() {
+        tokens.next();
+        expr = Expr::Or(Box::new(expr), Box::new(parse_and(tokens, timestamps)?));
+    }
+    Ok(expr)
+}
+
+fn parse_and(tokens: &mut Tokens, timestamps: &TimestampParser) -> Result<Expr, String> {
+    let mut expr = parse_not(tokens, timestamps)?;
+    loop {
+        match tokens.peek() {
+            Some(Token::And) => {
+                tokens.next();
+            },
+            Some(Token::Or) | Some(Token::Close) | None => break,
+            Some(_) => (),
+        }
+        expr = Expr::And(Box::new(expr), Box::new(parse_not(tokens, timestamps)?));
+    }
+    Ok(expr)
+}
+
+fn parse_not(tokens: &mut Tokens, timestamps: &TimestampParser) -> Result<Expr, String> {
+    match tokens.next() {
+        Some(Token::Not) => Ok(Expr::Not(Box::new(parse_not(tokens, timestamps)?))),
+        Some(Token::Open) => {
+            let expr = parse_or(tokens, timestamps)?;
+            match tokens.next() {
+                Some(Token::Close) => Ok(expr),
+                _ => Err("Missing )".to_string()),
+            }
+        },
+        Some(Token::Text(regex)) => Ok(Expr::Text(regex)),
+        Some(Token::Field(name, op, value, text)) => parse_field(name, op, value, &text, timestamps),
+        Some(token) => Err(format!("Unexpected {}", describe(&token))),
+        None => Err("Unexpected end of query".to_string()),
+    }
+}
+
+fn parse_field(name: String, op: Op, value: String, text: &str, timestamps: &TimestampParser) -> Result<Expr, String> {
+    let text = text_regex(text)?;
+    if name == TIME_FIELD {
+        let time = match parse_relative_time(&value) {
+            Some(Ok(delta)) => Value::TimeAgo(delta),
+            Some(Err(error)) => return Err(error),
+            None => Value::Time(timestamps.parse(&value).ok_or_else(|| format!("Invalid time {}", value))?),
+        };
+        return Ok(Expr::Field(name, op, time, text));
+    }
+
+    let value = match op {
+        Op::Match | Op::NoMatch => Value::Regex(Regex::new(&value).map_err(|error| format!("Invalid regex {}: {}", value, error))?),
+        _ => Value::Text(value),
+    };
+    Ok(Expr::Field(name, op, value, text))
+}
+
+/// Duration before now, e.g. `-15m`, `-2h` or `-1d`. Returns none unless the value
+/// looks like one, and error when it is too long.
+fn parse_relative_time(value: &str) -> Option<Result<TimeDelta, String>> {
+    let amount_unit = value.strip_prefix('-')?;
+    let unit = amount_unit.chars().last()?;
+    let amount = amount_unit[..amount_unit.len() - unit.len_utf8()].parse::<u64>().ok()?;
+    let to_delta = match unit {
+        's' => TimeDelta::try_seconds,
+        'm' => TimeDelta::try_minutes,
+        'h' => TimeDelta::try_hours,
+        'd' => TimeDelta::try_days,
+        _ => return None,
+    };
+    let delta = i64::try_from(amount).ok()
+        .and_then(to_delta)
+        .filter(|delta| Utc::now().checked_sub_signed(*delta).is_some());
+    Some(delta.ok_or_else(|| format!("Time {} is out of range", value)))
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || ['_', '.', '@', '-'].contains(&c)
+}
+
+fn is_word_end(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')'
+}
+
+fn text_regex(text: &str) -> Result<Regex, String> {
+    RegexBuilder::new(&regex::escape(text))
+        .case_insensitive(true)
+        .build()
+        .map_err(|error| error.to_string())
+}
+
+fn read_quoted(chars: &mut Peekable<Chars>, quote: char) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some('\\') => match chars.next() {
+                // Keep escapes in regex literals, except for the delimiter
+                Some(c) if quote == '/' && c != '/' => {
+                    text.push('\\');
+                    text.push(c);
+                },
+                Some(c) => text.push(c),
+                None => return Err(format!("Missing closing {}", quote)),
+            },
+            Some(c) if c == quote => return Ok(text),
+            Some(c) => text.push(c),
+            None => return Err(format!("Missing closing {}", quote)),
+        }
+    }
+}
+
+fn read_op(chars: &mut Peekable<Chars>) -> Option<Op> {
+    let op = match chars.peek()? {
+        '=' => Op::Eq,
+        '~' => Op::Match,
+        '!' => Op::Ne,
+        '>' => Op::Gt,
+        '<' => Op::Lt,
+        _ => return None,
+    };
+    chars.next();
+    if chars.next_if_eq(&'=').is_some() {
+        return match op {
+            Op::Gt => Some(Op::Ge),
+            Op::Lt => Some(Op::Le),
+            Op::Ne | Op::Eq => Some(op),
+            _ => None,
+        };
+    }
+    if op == Op::Ne {
+        return chars.next_if_eq(&'~').map(|_| Op::NoMatch);
+    }
+    Some(op)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        match c {
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            },
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            },
+            '"' => {
+                chars.next();
+                tokens.push(Token::Text(text_regex(&read_quoted(&mut chars, '"')?)?));
+            },
+            '/' => {
+                chars.next();
+                let pattern = read_quoted(&mut chars, '/')?;
+                let case_insensitive = chars.next_if_eq(&'i').is_some();
+                let regex = RegexBuilder::new(&pattern)
+                    .case_insensitive(case_insensitive)
+                    .build()
+                    .map_err(|error| format!("Invalid regex {}: {}", pattern, error))?;
+                tokens.push(Token::Text(regex));
+            },
+            '!' | '-' => {
+                chars.next();
+                let negates = chars.peek().is_some_and(|next| !is_word_end(*next) && (c != '-' || !next.is_ascii_digit()));
+                if negates {
+                    tokens.push(Token::Not);
+                } else {
+                    let mut word = c.to_string();
+                    while let Some(c) = chars.next_if(|c| !is_word_end(*c)) {
+                        word.push(c);
+                    }
+                    tokens.push(Token::Text(text_regex(&word)?));
+                }
+            },
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| is_name_char(*c)) {
+                    word.push(c);
+                }
+
+                if !word.is_empty() {
+                    let mut lookahead = chars.clone();
+                    if let Some(op) = read_op(&mut lookahead) {
+                        chars = lookahead;
+                        let (value, text) = if chars.next_if_eq(&'"').is_some() {
+                            let value = read_quoted(&mut chars, '"')?;
+                            let text = format!("{}{}\"{}\"", word, op.symbol(), value);
+                            (value, text)
+                        } else {
+                            let mut value = String::new();
+                            while let Some(c) = chars.next_if(|c| !is_word_end(*c)) {
+                                value.push(c);
+                            }
+                            let text = format!("{}{}{}", word, op.symbol(), value);
+                            (value, text)
+                        };
+                        tokens.push(Token::Field(word, op, value, text));
+                        continue;
+                    }
+                }
+
+                while let Some(c) = chars.next_if(|c| !is_word_end(*c)) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "AND" | "&&" => tokens.push(Token::And),
+                    "OR" | "||" => tokens.push(Token::Or),
+                    "NOT" => tokens.push(Token::Not),
+                    _ => tokens.push(Token::Text(text_regex(&word)?)),
+                }
+            },
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, TimeDelta, Utc};
+    use crate::{JsonParser, Query, Record, RecordParser};
+
+    fn matches(query: &str, line: &str) -> bool {
+        let record = JsonParser::default().parse(line);
+        Query::parse(query).unwrap().matches(line, record.as_ref(), None)
+    }
+
+    #[test]
+    fn text_terms() {
+        assert!(matches("error", "2023-10-11 ERROR Disk full"));
+        assert!(matches("error disk", "2023-10-11 ERROR Disk full"));
+        assert!(!matches("error memory", "2023-10-11 ERROR Disk full"));
+        assert!(matches(r#""disk full""#, "ERROR Disk full"));
+        assert!(!matches(r#""full disk""#, "ERROR Disk full"));
+        assert!(matches("/Disk \\w+$/", "ERROR Disk full"));
+        assert!(!matches("/disk/", "ERROR Disk full"));
+        assert!(matches("/disk/i", "ERROR Disk full"));
+        assert!(matches("", "anything"));
+    }
+
+    #[test]
+    fn boolean_operators() {
+        assert!(matches("warn OR error", "ERROR Disk full"));
+        assert!(matches("NOT warn", "ERROR Disk full"));
+        assert!(!matches("-disk", "ERROR Disk full"));
+        assert!(!matches("!disk", "ERROR Disk full"));
+        assert!(matches("(warn OR error) AND disk", "ERROR Disk full"));
+        assert!(!matches("warn OR error AND memory", "ERROR Disk full"));
+        assert!(matches("-15", "Temperature -15 degrees"));
+    }
+
+    #[test]
+    fn field_comparisons() {
+        let line = r#"{"level":"warn","msg":"Slow request","latency_ms":740,"path":"/api/users","user":42}"#;
+        assert!(matches("level>=warn", line));
+        assert!(matches("level>info", line));
+        assert!(!matches("level>=error", line));
+        assert!(matches("latency_ms>500", line));
+        assert!(!matches("latency_ms<500", line));
+        assert!(matches("user=42 path~^/api", line));
+        assert!(matches("path!~^/admin", line));
+        assert!(!matches("path!~^/api", line));
+        assert!(matches(r#"message="slow request""#, line));
+        assert!(matches("user!=7", line));
+        assert!(!matches("host=web1", line));
+        assert!(!matches("NOT host=web1 AND host!=web1", line));
+        assert!(!matches("level>=warn", "WARN plain line without record"));
+        assert!(matches("user=42", "login ok USER=42 from web1"));
+        assert!(matches(r#"msg="slow request""#, r#"warn msg="Slow request" latency=740"#));
+        assert!(!matches("user=42", "login ok user=7"));
+    }
+
+    #[test]
+    fn time_ranges() {
+        let query = Query::parse("time>=2023-10-11T22:00:00Z time<\"2023-10-11 23:00:00Z\"").unwrap();
+        assert!(query.needs_time());
+        assert!(!query.needs_record());
+        let time = |text: &str| Some(DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc));
+        assert!(query.matches("", None, time("2023-10-11T22:30:00Z")));
+        assert!(!query.matches("", None, time("2023-10-11T23:30:00Z")));
+        assert!(!query.matches("", None, None));
+
+        let query = Query::parse("time>-15m").unwrap();
+        assert!(query.matches("", None, Some(Utc::now())));
+        assert!(!query.matches("", None, time("2023-10-11T23:30:00Z")));
+        assert!(query.matches("", None, Some(Utc::now() - TimeDelta::minutes(14))));
+        assert!(!query.matches("", None, Some(Utc::now() - TimeDelta::minutes(16))));
+    }
+
+    #[test]
+    fn numeric_levels() {
+        let record = Record { level: Some("50".to_string()), ..Default::default() };
+        assert!(Query::parse("level=error").unwrap().matches("", Some(&record), None));
+        assert!(Query::parse("level>warn").unwrap().matches("", Some(&record), None));
+
+        let record = Record { message: "Disk failure".to_string(), ..Default::default() };
+        assert!(!Query::parse("level=error").unwrap().matches("", Some(&record), None));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert!(Query::parse("(error").is_err());
+        assert!(Query::parse("error)").is_err());
+        assert!(Query::parse("error OR").is_err());
+        assert!(Query::parse("\"unterminated").is_err());
+        assert!(Query::parse("/(/").is_err());
+        assert!(Query::parse("time>yesterday").is_err());
+        assert!(Query::parse("path~(").is_err());
+        assert!(Query::parse("time>-99999999999999d").is_err());
+        assert!(Query::parse("time>-99999999999999999999s").is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::mpsc::{SendError, Sender};
+use crate::Message;
+
+#[derive(Clone)]
+enum Channel {
+    Std(Sender<Message>),
+    #[cfg(feature = "async")]
+    Async(futures::channel::mpsc::UnboundedSender<Message>),
+}
+
+/// Sending end of the channel watch clients deliver their messages to.
+///
+/// Clients do not care whether the owner reads messages with blocking std receiver
+/// or polls them as a stream, so both channel kinds are hidden behind this type.
+#[derive(Clone)]
+pub struct MessageSender {
+    channel: Channel,
+}
+
+impl MessageSender {
+    pub fn send(&self, msg: Message) -> Result<(), SendError<Message>> {
+        match &self.channel {
+            Channel::Std(tx) => tx.send(msg),
+            #[cfg(feature = "async")]
+            Channel::Async(tx) => tx.unbounded_send(msg).map_err(|error| SendError(error.into_inner())),
+        }
+    }
+}
+
+impl From<Sender<Message>> for MessageSender {
+    fn from(tx: Sender<Message>) -> Self {
+        Self {
+            channel: Channel::Std(tx),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<futures::channel::mpsc::UnboundedSender<Message>> for MessageSender {
+    fn from(tx: futures::channel::mpsc::UnboundedSender<Message>) -> Self {
+        Self {
+            channel: Channel::Async(tx),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::{Stream, StreamExt};
+use crate::{ClientInfo, Message};
+
+/// Async counterpart of `Tailor::watch`, created with `Tailor::watch_stream`.
+///
+/// Watch client writes messages straight into the futures channel this stream polls,
+/// so there is no blocking bridge thread in between. Dropping the stream stops the watch.
+pub struct MessageStream {
+    rx: UnboundedReceiver<Message>,
+    client_info: ClientInfo,
+}
+
+impl MessageStream {
+    pub(crate) fn new(rx: UnboundedReceiver<Message>, client_info: ClientInfo) -> Self {
+        Self {
+            rx,
+            client_info,
+        }
+    }
+}
+
+impl Stream for MessageStream {
+    type Item = Message;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for MessageStream {
+    fn drop(&mut self) {
+        // Client thread notices stop request within a second, no need to block async executor on join
+        if self.client_info.tx.send(true).is_err() {
+            log::warn!("Failed to send stop message to stream client thread.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::time::Duration;
+    use futures::executor::block_on;
+    use futures::StreamExt;
+    use crate::{Message, Tailor};
+    use crate::testutil::create_test_file;
+
+    const TEST_DIR: &str = "./test/stream";
+
+    fn next_lines(stream: &mut crate::MessageStream) -> Vec<String> {
+        loop {
+            match block_on(stream.next()) {
+                Some(Message::NewLines(lines)) => return lines,
+                Some(_) => continue,
+                None => return vec![],
+            }
+        }
+    }
+
+    #[test]
+    fn initial_read() {
+        let test_file = create_test_file(TEST_DIR, "initial_read.txt", &["Line1", "Line2"]);
+        let tailor = Tailor::new().unwrap();
+        let mut stream = tailor.watch_stream(test_file);
+
+        assert_eq!(next_lines(&mut stream), vec!["Line1".to_string(), "Line2".to_string()]);
+    }
+
+    #[test]
+    fn new_content() {
+        let test_file = create_test_file(TEST_DIR, "new_content.txt", &["Line1"]);
+        let tailor = Tailor::new().unwrap();
+        let mut stream = tailor.watch_stream(test_file.clone());
+        let _initial_read = next_lines(&mut stream);
+
+        std::thread::sleep(Duration::from_millis(100));
+        let mut file = OpenOptions::new().append(true).open(test_file).unwrap();
+        assert!(writeln!(file, "New content").is_ok());
+        assert!(file.flush().is_ok());
+
+        assert_eq!(next_lines(&mut stream), vec!["New content".to_string()]);
+    }
+}
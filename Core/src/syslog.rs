@@ -0,0 +1,215 @@
+const FACILITY_NAMES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news",
+    "uucp", "cron", "authpriv", "ftp", "ntp", "security", "console", "solaris-cron",
+    "local0", "local1", "local2", "local3", "local4", "local5", "local6", "local7",
+];
+const SEVERITY_NAMES: [&str; 8] = ["emerg", "alert", "crit", "err", "warning", "notice", "info", "debug"];
+const NIL_VALUE: &str = "-";
+const BOM: &str = "\u{feff}";
+
+/// Syslog message parsed from RFC 3164 (BSD) or RFC 5424 frame
+#[derive(Debug, PartialEq)]
+pub struct SyslogMessage {
+    pub facility: u8,
+    pub severity: u8,
+    pub timestamp: Option<String>,
+    pub host: Option<String>,
+    pub app_name: Option<String>,
+    pub message: String,
+}
+
+impl SyslogMessage {
+    /// Parse syslog frame. Returns none if frame does not start with valid `<PRI>` part.
+    pub fn parse(frame: &str) -> Option<Self> {
+        let frame = frame.trim_end_matches(['\r', '\n', '\0']);
+        let (priority, rest) = Self::parse_priority(frame)?;
+        let facility = priority / 8;
+        let severity = priority % 8;
+
+        if let Some(rest) = rest.strip_prefix("1 ") {
+            return Some(Self::parse_rfc5424(facility, severity, rest));
+        }
+
+        Some(Self::parse_rfc3164(facility, severity, rest))
+    }
+
+    pub fn facility_name(&self) -> &'static str {
+        FACILITY_NAMES.get(self.facility as usize).unwrap_or(&"unknown")
+    }
+
+    pub fn severity_name(&self) -> &'static str {
+        SEVERITY_NAMES.get(self.severity as usize).unwrap_or(&"unknown")
+    }
+
+    /// Format message as log line: `timestamp host facility.severity app: message`
+    pub fn to_line(&self) -> String {
+        format!("{} {} {}.{} {}: {}",
+            self.timestamp.as_deref().unwrap_or(NIL_VALUE),
+            self.host.as_deref().unwrap_or(NIL_VALUE),
+            self.facility_name(),
+            self.severity_name(),
+            self.app_name.as_deref().unwrap_or(NIL_VALUE),
+            self.message)
+    }
+
+    fn parse_priority(frame: &str) -> Option<(u8, &str)> {
+        let rest = frame.strip_prefix('<')?;
+        let end = rest.find('>')?;
+        if end == 0 || end > 3 {
+            return None;
+        }
+        let priority = rest[..end].parse::<u8>().ok()?;
+        if priority > 191 {
+            return None;
+        }
+
+        Some((priority, &rest[end + 1..]))
+    }
+
+    /// `TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA [MSG]`
+    fn parse_rfc5424(facility: u8, severity: u8, rest: &str) -> Self {
+        let mut parts = rest.splitn(5, ' ');
+        let mut next_value = || parts.next().filter(|value| *value != NIL_VALUE).map(String::from);
+        let timestamp = next_value();
+        let host = next_value();
+        let app_name = next_value();
+        let _proc_id = next_value();
+        let rest = parts.next().unwrap_or("");
+        // MSGID is the first token of the rest, skip it together with structured data
+        let rest = rest.split_once(' ').map(|(_, rest)| rest).unwrap_or("");
+        let message = Self::skip_structured_data(rest);
+
+        SyslogMessage {
+            facility,
+            severity,
+            timestamp,
+            host,
+            app_name,
+            message: message.trim_start_matches(BOM).to_string(),
+        }
+    }
+
+    fn skip_structured_data(rest: &str) -> &str {
+        if let Some(message) = rest.strip_prefix(NIL_VALUE) {
+            return message.strip_prefix(' ').unwrap_or(message);
+        }
+
+        let mut in_element = false;
+        let mut in_quotes = false;
+        let mut escaped = false;
+        for (idx, c) in rest.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_quotes => escaped = true,
+                '"' if in_element => in_quotes = !in_quotes,
+                '[' if !in_element => in_element = true,
+                ']' if in_element && !in_quotes => in_element = false,
+                ' ' if !in_element => return &rest[idx + 1..],
+                _ if !in_element => return &rest[idx..],
+                _ => (),
+            }
+        }
+
+        ""
+    }
+
+    /// `Mmm dd hh:mm:ss HOSTNAME TAG: MSG`. Timestamp and hostname are optional in practice.
+    fn parse_rfc3164(facility: u8, severity: u8, rest: &str) -> Self {
+        let (timestamp, rest) = match rest.get(..15) {
+            Some(timestamp) if Self::is_bsd_timestamp(timestamp) => {
+                (Some(timestamp.to_string()), rest[15..].trim_start())
+            },
+            _ => (None, rest),
+        };
+
+        let (host, rest) = match timestamp {
+            Some(_) => match rest.split_once(' ') {
+                Some((host, rest)) if !host.ends_with(':') && !host.contains('[') => (Some(host.to_string()), rest),
+                _ => (None, rest),
+            },
+            None => (None, rest),
+        };
+
+        let (app_name, message) = match rest.split_once(": ") {
+            Some((tag, message)) if !tag.is_empty() && !tag.contains(' ') => {
+                let app_name = tag.split('[').next().unwrap_or(tag);
+                (Some(app_name.to_string()), message)
+            },
+            _ => (None, rest),
+        };
+
+        SyslogMessage {
+            facility,
+            severity,
+            timestamp,
+            host,
+            app_name,
+            message: message.to_string(),
+        }
+    }
+
+    fn is_bsd_timestamp(timestamp: &str) -> bool {
+        let bytes = timestamp.as_bytes();
+        bytes.len() == 15
+            && bytes[..3].iter().all(|b| b.is_ascii_alphabetic())
+            && bytes[3] == b' '
+            && (bytes[4] == b' ' || bytes[4].is_ascii_digit())
+            && bytes[5].is_ascii_digit()
+            && bytes[6] == b' '
+            && bytes[9] == b':'
+            && bytes[12] == b':'
+            && [7, 8, 10, 11, 13, 14].iter().all(|idx| bytes[*idx].is_ascii_digit())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SyslogMessage;
+
+    #[test]
+    fn parse_rfc3164() {
+        let msg = SyslogMessage::parse("<34>Oct 11 22:14:15 mymachine su[123]: 'su root' failed for lonvick on /dev/pts/8").unwrap();
+        assert_eq!(msg.facility_name(), "auth");
+        assert_eq!(msg.severity_name(), "crit");
+        assert_eq!(msg.timestamp.as_deref(), Some("Oct 11 22:14:15"));
+        assert_eq!(msg.host.as_deref(), Some("mymachine"));
+        assert_eq!(msg.app_name.as_deref(), Some("su"));
+        assert_eq!(msg.message, "'su root' failed for lonvick on /dev/pts/8");
+    }
+
+    #[test]
+    fn parse_rfc3164_without_header() {
+        let msg = SyslogMessage::parse("<13>Link is down").unwrap();
+        assert_eq!(msg.facility_name(), "user");
+        assert_eq!(msg.severity_name(), "notice");
+        assert_eq!(msg.host, None);
+        assert_eq!(msg.message, "Link is down");
+    }
+
+    #[test]
+    fn parse_rfc5424() {
+        let msg = SyslogMessage::parse("<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut=\"3\" eventSource=\"App\\]lication\"] An application event").unwrap();
+        assert_eq!(msg.facility_name(), "local4");
+        assert_eq!(msg.severity_name(), "notice");
+        assert_eq!(msg.timestamp.as_deref(), Some("2003-10-11T22:14:15.003Z"));
+        assert_eq!(msg.host.as_deref(), Some("mymachine.example.com"));
+        assert_eq!(msg.app_name.as_deref(), Some("evntslog"));
+        assert_eq!(msg.message, "An application event");
+    }
+
+    #[test]
+    fn parse_rfc5424_nil_values() {
+        let msg = SyslogMessage::parse("<11>1 - - - - - - \u{feff}Disk failure\n").unwrap();
+        assert_eq!(msg.severity_name(), "err");
+        assert_eq!(msg.timestamp, None);
+        assert_eq!(msg.host, None);
+        assert_eq!(msg.message, "Disk failure");
+        assert_eq!(msg.to_line(), "- - user.err -: Disk failure");
+    }
+
+    #[test]
+    fn reject_invalid_priority() {
+        assert_eq!(SyslogMessage::parse("no priority"), None);
+        assert_eq!(SyslogMessage::parse("<192>1 - - - - - -"), None);
+    }
+}
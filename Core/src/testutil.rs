@@ -0,0 +1,21 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Create file with given lines in the test folder, replacing the one from previous run
+pub fn create_test_file(dir: &str, filename: &str, contents: &[impl AsRef<str>]) -> PathBuf {
+    let mut file_path = PathBuf::from(dir);
+    assert!(std::fs::create_dir_all(&file_path).is_ok());
+    file_path.push(filename);
+
+    if std::fs::remove_file(&file_path).is_err() {
+        log::error!("Failed to remove test file.");
+    }
+
+    let mut file = File::create(file_path.as_path()).unwrap();
+    for line in contents {
+        assert!(writeln!(file, "{}", line.as_ref()).is_ok());
+    }
+
+    file_path
+}
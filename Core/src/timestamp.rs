@@ -0,0 +1,327 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use crate::Record;
+
+/// Locates timestamps in lines: ISO 8601 / RFC 3339, Apache `10/Oct/2000:13:55:36 -0700`
+/// and syslog `Oct 11 22:14:15`
+const TIMESTAMP_PATTERN: &str = concat!(
+    r"(?P<iso>\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(?::\d{2}(?:[.,]\d+)?)?(?:\s?(?:Z|[+-]\d{2}(?::?\d{2})?))?)",
+    r"|(?P<apache>\d{2}/[A-Z][a-z]{2}/\d{4}:\d{2}:\d{2}:\d{2}(?: [+-]\d{4})?)",
+    r"|(?P<syslog>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}(?:\.\d+)?)",
+);
+/// Epoch seconds or milliseconds, only recognised at the start of line
+const EPOCH_PATTERN: &str = r"^\[?(?P<epoch>\d{10}(?:\.\d+)?|\d{13})\b";
+/// Syslog timestamps without year which would be further in the future are from the last year
+const FUTURE_TOLERANCE_DAYS: i64 = 1;
+
+/// Finds and parses timestamps of log lines, normalizing them to UTC
+///
+/// Timestamps without time zone are read in the default zone, local unless set with
+/// `with_zone`. Timestamps without year, e.g. syslog ones, get the year which makes them
+/// closest to now without being in the future. Custom `strftime` formats are tried
+/// before the built-in ones, at the start of line.
+#[derive(Clone)]
+pub struct TimestampParser {
+    zone: Option<Tz>,
+    formats: Vec<String>,
+    now: Option<DateTime<Utc>>,
+    regex: Regex,
+    epoch_regex: Regex,
+}
+
+impl Default for TimestampParser {
+    fn default() -> Self {
+        Self {
+            zone: None,
+            formats: vec![],
+            now: None,
+            regex: Regex::new(TIMESTAMP_PATTERN).unwrap(),
+            epoch_regex: Regex::new(EPOCH_PATTERN).unwrap(),
+        }
+    }
+}
+
+impl TimestampParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Zone of timestamps which do not tell it
+    pub fn with_zone(mut self, zone: Tz) -> Self {
+        self.zone = Some(zone);
+        self
+    }
+
+    /// Zone given by IANA name, e.g. `Europe/Berlin` or `UTC`
+    pub fn with_zone_name(self, name: &str) -> Result<Self, String> {
+        match name.parse::<Tz>() {
+            Ok(zone) => Ok(self.with_zone(zone)),
+            Err(error) => Err(format!("Unknown time zone {}: {}", name, error)),
+        }
+    }
+
+    /// Also recognise timestamps in `strftime` format, e.g. `%d.%m.%Y %H:%M:%S`
+    pub fn with_format(mut self, format: &str) -> Self {
+        self.formats.push(format.to_string());
+        self
+    }
+
+    /// Time used to complete timestamps without year or date, current time by default
+    pub fn with_now(mut self, now: DateTime<Utc>) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Find timestamp in the line
+    pub fn find(&self, line: &str) -> Option<DateTime<Utc>> {
+        let start = line.trim_start().trim_start_matches('[');
+        for format in &self.formats {
+            if let Some(time) = self.parse_format(start, format) {
+                return Some(time);
+            }
+        }
+
+        if let Some(captures) = self.epoch_regex.captures(line) {
+            return Self::parse_epoch(&captures["epoch"]);
+        }
+
+        let captures = self.regex.captures(line)?;
+        if let Some(iso) = captures.name("iso") {
+            self.parse_iso(iso.as_str())
+        } else if let Some(apache) = captures.name("apache") {
+            self.parse_apache(apache.as_str())
+        } else {
+            self.parse_syslog(captures.name("syslog")?.as_str())
+        }
+    }
+
+    /// Parse timestamp text as a whole, e.g. timestamp of parsed record.
+    /// Numbers are read as epoch seconds, milli-, micro- or nanoseconds, by magnitude.
+    pub fn parse(&self, text: &str) -> Option<DateTime<Utc>> {
+        let text = text.trim();
+        if text.chars().next().is_some_and(|c| c.is_ascii_digit()) && text.parse::<f64>().is_ok() {
+            return Self::parse_epoch(text);
+        }
+
+        self.find(text)
+    }
+
+    /// Returns none for numbers beyond the time range, e.g. `20231011221415`
+    fn parse_epoch(text: &str) -> Option<DateTime<Utc>> {
+        let (whole_text, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let whole = whole_text.parse::<i64>().ok()?;
+        let nanos = match whole_text.len() {
+            0..=11 => {
+                let fraction = format!("{:0<9}", fraction.get(..9).unwrap_or(fraction));
+                whole.checked_mul(1_000_000_000)?.checked_add(fraction.parse::<i64>().ok()?)?
+            },
+            12..=14 => whole.checked_mul(1_000_000)?,
+            15..=17 => whole.checked_mul(1_000)?,
+            _ => whole,
+        };
+
+        Some(Utc.timestamp_nanos(nanos))
+    }
+
+    fn parse_iso(&self, text: &str) -> Option<DateTime<Utc>> {
+        let mut text = text.replacen(' ', "T", 1).replace(',', ".");
+        text.retain(|c| c != ' ');
+        // Offsets written as +0200 or +02
+        let offset_start = text.rfind(['+', '-']).filter(|idx| *idx > 10);
+        if let Some(idx) = offset_start {
+            match text.len() - idx {
+                3 => text.push_str(":00"),
+                5 => text.insert(idx + 3, ':'),
+                _ => (),
+            }
+        }
+
+        if let Ok(time) = DateTime::parse_from_rfc3339(&text) {
+            return Some(time.with_timezone(&Utc));
+        }
+
+        ["%Y-%m-%dT%H:%M:%S%.f%:z", "%Y-%m-%dT%H:%M%:z"].iter()
+            .find_map(|format| DateTime::parse_from_str(&text, format).ok())
+            .map(|time| time.with_timezone(&Utc))
+            .or_else(|| {
+                ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"].iter()
+                    .find_map(|format| NaiveDateTime::parse_from_str(&text, format).ok())
+                    .and_then(|time| self.in_zone(time))
+            })
+    }
+
+    fn parse_apache(&self, text: &str) -> Option<DateTime<Utc>> {
+        match DateTime::parse_from_str(text, "%d/%b/%Y:%H:%M:%S %z") {
+            Ok(time) => Some(time.with_timezone(&Utc)),
+            Err(_) => self.in_zone(NaiveDateTime::parse_from_str(text, "%d/%b/%Y:%H:%M:%S").ok()?),
+        }
+    }
+
+    fn parse_syslog(&self, text: &str) -> Option<DateTime<Utc>> {
+        self.parse_without_year(text, "%b %e %H:%M:%S%.f")
+    }
+
+    fn parse_format(&self, text: &str, format: &str) -> Option<DateTime<Utc>> {
+        if let Ok((time, _)) = DateTime::parse_and_remainder(text, format) {
+            return Some(time.with_timezone(&Utc));
+        }
+        if let Ok((time, _)) = NaiveDateTime::parse_and_remainder(text, format) {
+            return self.in_zone(time);
+        }
+
+        let has_year = ["%Y", "%y", "%G", "%g", "%C", "%F", "%D", "%s"].iter().any(|spec| format.contains(spec));
+        let has_date = ["%d", "%e", "%j", "%F", "%D", "%s"].iter().any(|spec| format.contains(spec));
+        if has_date && !has_year {
+            return self.parse_without_year(text, format);
+        }
+        if !has_date {
+            let (time, _) = NaiveTime::parse_and_remainder(text, format).ok()?;
+            return self.in_zone(self.today().and_time(time));
+        }
+
+        None
+    }
+
+    /// Pick year making the time closest to now, but not in the future
+    fn parse_without_year(&self, text: &str, format: &str) -> Option<DateTime<Utc>> {
+        let now = self.now();
+        let year = now.year();
+        let parse = |year: i32| {
+            NaiveDateTime::parse_and_remainder(&format!("{} {}", year, text), &format!("%Y {}", format))
+                .ok()
+                .and_then(|(time, _)| self.in_zone(time))
+        };
+
+        // Feb 29 only exists in leap years, look for previous one
+        let time = parse(year).or_else(|| (1..8).find_map(|years_back| parse(year - years_back)))?;
+        if time > now + Duration::days(FUTURE_TOLERANCE_DAYS) {
+            return parse(year - 1).or(Some(time));
+        }
+
+        Some(time)
+    }
+
+    fn in_zone(&self, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self.zone {
+            Some(zone) => zone.from_local_datetime(&time).earliest().map(|time| time.with_timezone(&Utc)),
+            None => Local.from_local_datetime(&time).earliest().map(|time| time.with_timezone(&Utc)),
+        }
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        self.now.unwrap_or_else(Utc::now)
+    }
+
+    fn today(&self) -> NaiveDate {
+        match self.zone {
+            Some(zone) => self.now().with_timezone(&zone).date_naive(),
+            None => self.now().with_timezone(&Local).date_naive(),
+        }
+    }
+}
+
+/// Assigns times to consecutive lines of log
+///
+/// Lines without timestamp, e.g. stack trace lines, inherit time of the previous line.
+pub struct TimestampTracker {
+    parser: TimestampParser,
+    last: Option<DateTime<Utc>>,
+}
+
+impl TimestampTracker {
+    pub fn new(parser: TimestampParser) -> Self {
+        Self {
+            parser,
+            last: None,
+        }
+    }
+
+    /// Time of the next line. Timestamp of the parsed record is preferred when given.
+    pub fn push(&mut self, line: &str, record: Option<&Record>) -> Option<DateTime<Utc>> {
+        let time = match record.and_then(|record| record.timestamp.as_deref()) {
+            Some(timestamp) => self.parser.parse(timestamp),
+            None => self.parser.find(line),
+        };
+        if time.is_some() {
+            self.last = time;
+        }
+
+        self.last
+    }
+
+    /// Forget time of the previous line, e.g. when log file was replaced
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, TimeZone, Utc};
+    use crate::{Record, TimestampParser, TimestampTracker};
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    fn parser() -> TimestampParser {
+        TimestampParser::new()
+            .with_zone_name("Europe/Berlin").unwrap()
+            .with_now(utc("2023-10-12T12:00:00Z"))
+    }
+
+    #[test]
+    fn iso_and_rfc3339() {
+        let parser = parser();
+        assert_eq!(parser.find("2023-10-11T22:14:15.003Z switch app: Port up"), Some(utc("2023-10-11T22:14:15.003Z")));
+        assert_eq!(parser.find("[2023-10-11T22:14:15+0200 INFO app] Started"), Some(utc("2023-10-11T20:14:15Z")));
+        assert_eq!(parser.find("2023-10-11 22:14:15,003 ERROR [main] App - Failed"), Some(utc("2023-10-11T20:14:15.003Z")));
+        assert_eq!(parser.find(r#"{"level":"info","time":"2023-10-11T22:14:15-05:00"}"#), Some(utc("2023-10-12T03:14:15Z")));
+        assert_eq!(parser.find("no time here"), None);
+    }
+
+    #[test]
+    fn apache_and_syslog() {
+        let parser = parser();
+        assert_eq!(parser.find(r#"127.0.0.1 - - [10/Oct/2000:13:55:36 -0700] "GET / HTTP/1.0" 200 2326"#), Some(utc("2000-10-10T20:55:36Z")));
+        assert_eq!(parser.find("Oct 11 22:14:15 router sshd[42]: Login failed"), Some(utc("2023-10-11T20:14:15Z")));
+        // Without year, December logs read in October are from the last year
+        assert_eq!(parser.find("Dec 31 23:59:59 router cron: Done"), Some(utc("2022-12-31T22:59:59Z")));
+    }
+
+    #[test]
+    fn epoch() {
+        let parser = parser();
+        assert_eq!(parser.find("1697062455 job started"), Some(Utc.timestamp_opt(1697062455, 0).unwrap()));
+        assert_eq!(parser.find("1697062455123 job started"), Some(Utc.timestamp_millis_opt(1697062455123).unwrap()));
+        assert_eq!(parser.parse("1697062455.5"), Some(Utc.timestamp_millis_opt(1697062455500).unwrap()));
+        assert_eq!(parser.parse("1697062455123456"), Some(Utc.timestamp_micros(1697062455123456).unwrap()));
+        assert_eq!(parser.find("Took 1697062455 ms"), None);
+        assert_eq!(parser.parse("20231011221415"), None);
+        assert_eq!(parser.parse("99999999999"), None);
+        assert_eq!(parser.parse("99999999999999999999"), None);
+    }
+
+    #[test]
+    fn custom_formats() {
+        let parser = parser().with_format("%d.%m.%Y %H:%M:%S").with_format("%d.%m. %H:%M");
+        assert_eq!(parser.find("11.10.2023 22:14:15 Started"), Some(utc("2023-10-11T20:14:15Z")));
+        assert_eq!(parser.find("11.10. 22:14 Started"), Some(utc("2023-10-11T20:14:00Z")));
+
+        let parser = parser.with_format("%H:%M:%S");
+        assert_eq!(parser.find("09:30:00 [INFO] Watching"), Some(utc("2023-10-12T07:30:00Z")));
+        assert!(TimestampParser::new().with_zone_name("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn inherit_time() {
+        let mut tracker = TimestampTracker::new(parser());
+        assert_eq!(tracker.push("Traceback (most recent call last):", None), None);
+        assert_eq!(tracker.push("2023-10-11 22:14:15,003 - app - ERROR - Failed", None), Some(utc("2023-10-11T20:14:15.003Z")));
+        assert_eq!(tracker.push("  File \"app.py\", line 1", None), Some(utc("2023-10-11T20:14:15.003Z")));
+
+        let record = Record { timestamp: Some("1697062455".to_string()), ..Default::default() };
+        assert_eq!(tracker.push("{}", Some(&record)), Some(Utc.timestamp_opt(1697062455, 0).unwrap()));
+    }
+}
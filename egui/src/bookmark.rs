@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+/// Identity of line surviving reloads: file it was read from and its byte offset there
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LineKey {
+    /// File within followed folder, none for the followed file or command itself
+    pub source: Option<PathBuf>,
+    pub offset: u64,
+}
+
+/// Marked line with optional note
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Bookmark {
+    key: LineKey,
+    note: String,
+    /// Line text when bookmarked, shown when the line is not loaded
+    text: String,
+}
+
+impl Bookmark {
+    pub fn new(key: LineKey, text: String) -> Self {
+        Self {
+            key,
+            note: String::new(),
+            text,
+        }
+    }
+
+    pub fn get_key(&self) -> &LineKey {
+        &self.key
+    }
+
+    pub fn get_note(&mut self) -> &mut String {
+        &mut self.note
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+}
@@ -1,8 +1,9 @@
-use egui::Color32;
+use egui::{Color32, Visuals};
 use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize};
+use tailor::LogLevel;
 
-#[derive(Serialize,Deserialize)]
+#[derive(Serialize,Deserialize,Clone,PartialEq)]
 pub struct Colors {
     pub foreground: [f32; 3],
     pub background: [f32; 3],
@@ -17,13 +18,81 @@ impl Default for Colors {
     }
 }
 
+pub fn rgb_to_color32(rgb: [f32; 3]) -> Color32 {
+    Color32::from_rgb((rgb[0]*255.0) as u8, (rgb[1]*255.0) as u8, (rgb[2]*255.0) as u8)
+}
+
+pub fn color32_to_rgb(color: Color32) -> [f32; 3] {
+    [color.r() as f32 / 255.0, color.g() as f32 / 255.0, color.b() as f32 / 255.0]
+}
+
+/// Relative luminance as defined by WCAG
+fn luminance(rgb: [f32; 3]) -> f32 {
+    let linear = |c: f32| if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) };
+    0.2126 * linear(rgb[0]) + 0.7152 * linear(rgb[1]) + 0.0722 * linear(rgb[2])
+}
+
+/// WCAG contrast ratio, from 1 to 21
+fn contrast(first: [f32; 3], second: [f32; 3]) -> f32 {
+    let (first, second) = (luminance(first), luminance(second));
+    (first.max(second) + 0.05) / (first.min(second) + 0.05)
+}
+
+fn is_dark(rgb: [f32; 3]) -> bool {
+    luminance(rgb) < 0.2
+}
+
+fn is_grey(rgb: [f32; 3]) -> bool {
+    let max = rgb.iter().copied().fold(f32::MIN, f32::max);
+    let min = rgb.iter().copied().fold(f32::MAX, f32::min);
+    max - min < 0.1
+}
+
+/// Colour with lightness inverted, keeping hue and saturation
+fn invert_lightness(rgb: [f32; 3]) -> [f32; 3] {
+    let shift = 1.0 - rgb.iter().copied().fold(f32::MIN, f32::max) - rgb.iter().copied().fold(f32::MAX, f32::min);
+    rgb.map(|c| (c + shift).clamp(0.0, 1.0))
+}
+
+/// Lowest contrast ratio of text kept by contrast adjustment
+const MIN_CONTRAST: f32 = 4.5;
+
 impl Colors {
+    /// Colours of the ui theme, for lines on the darkest or lightest background
+    pub fn from_visuals(visuals: &Visuals) -> Self {
+        Self {
+            foreground: color32_to_rgb(visuals.text_color()),
+            background: color32_to_rgb(visuals.extreme_bg_color),
+        }
+    }
+
     pub fn foreground(&self) -> Color32 {
-        Color32::from_rgb((self.foreground[0]*255.0) as u8, (self.foreground[1]*255.0) as u8, (self.foreground[2]*255.0) as u8)
+        rgb_to_color32(self.foreground)
     }
 
     pub fn background(&self) -> Color32 {
-        Color32::from_rgb((self.background[0]*255.0) as u8, (self.background[1]*255.0) as u8, (self.background[2]*255.0) as u8)
+        rgb_to_color32(self.background)
+    }
+
+    /// Colours readable on the background, e.g. of highlight written for dark background shown
+    /// on light one. Grey background of lightness other than the given one is replaced by it,
+    /// with text lightness inverted, then text is made lighter or darker until it has enough
+    /// contrast. Coloured backgrounds are kept.
+    pub fn adjusted_to(&self, background: [f32; 3]) -> Self {
+        let mut adjusted = self.clone();
+        if is_grey(self.background) && is_dark(self.background) != is_dark(background) {
+            adjusted.foreground = invert_lightness(adjusted.foreground);
+            adjusted.background = background;
+        }
+
+        let target = if is_dark(adjusted.background) { 1.0 } else { 0.0 };
+        for _ in 0..8 {
+            if contrast(adjusted.foreground, adjusted.background) >= MIN_CONTRAST {
+                break;
+            }
+            adjusted.foreground = adjusted.foreground.map(|c| c + (target - c) * 0.3);
+        }
+        adjusted
     }
 }
 
@@ -58,11 +127,50 @@ impl<'de> Deserialize<'de> for SerializableRegex {
     }
 }
 
+fn default_enabled() -> bool {
+    true
+}
+
+/// Part of line coloured by highlight
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum HighlightScope {
+    /// Whole line
+    #[default]
+    Line,
+    /// Only text matching the pattern
+    Match,
+}
+
+/// Colours lines matching pattern or of log level
+///
+/// Highlights are layered: the first matching line highlight colours the line, match
+/// highlights colour their matches over it, the ones higher in the list winning where
+/// matches overlap. Capture groups with own colours are coloured instead of whole matches,
+/// e.g. just the number of `user=(\d+)`.
 #[derive(Serialize,Deserialize)]
 pub struct Highlight {
     pattern: String,
     regex: SerializableRegex,
     colors: Colors,
+    /// Name of log level matched instead of the pattern, e.g. `ERROR`
+    #[serde(default)]
+    level: Option<String>,
+    /// Disabled highlights are kept but do not colour anything
+    #[serde(default = "default_enabled")]
+    is_enabled: bool,
+    #[serde(default)]
+    is_case_sensitive: bool,
+    /// Pattern matches only whole words
+    #[serde(default)]
+    is_whole_word: bool,
+    #[serde(default)]
+    scope: HighlightScope,
+    /// Colours of capture groups, starting from the first group, none to not colour group
+    #[serde(default)]
+    group_colors: Vec<Option<Colors>>,
+    /// Why the pattern could not be compiled, the last valid regex is used meanwhile
+    #[serde(skip)]
+    regex_error: Option<String>,
 }
 
 impl Default for Highlight {
@@ -71,25 +179,77 @@ impl Default for Highlight {
             pattern: String::from(""),
             regex: SerializableRegex::new(Regex::new("").unwrap()),
             colors: Colors::default(),
+            level: None,
+            is_enabled: true,
+            is_case_sensitive: false,
+            is_whole_word: false,
+            scope: HighlightScope::Line,
+            group_colors: vec![],
+            regex_error: None,
         }
     }
 
 }
 
 impl Highlight {
-    pub fn new(pattern: String, colors: Colors) -> Result<Self, String> {
-        if let Ok(regex) = Regex::new(format!(r"(?i){}", pattern.as_str()).as_str()) {
-            return Ok(Self {
-                pattern,
-                regex: SerializableRegex::new(regex),
-                colors,
-            });
+    /// Highlight lines of given level, as told by `tailor::LevelClassifier`
+    pub fn for_level(level: LogLevel, colors: Colors) -> Self {
+        Self {
+            level: Some(level.name().to_string()),
+            colors,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_matching(&self, line: &str, level: Option<LogLevel>) -> bool {
+        if !self.is_enabled {
+            return false;
+        }
+
+        match self.get_level() {
+            Some(highlight_level) => level == Some(highlight_level),
+            None => self.regex.regex.is_match(line),
+        }
+    }
+
+    /// Byte ranges of text coloured over the line and their colours: coloured capture groups
+    /// if there are any, otherwise whole matches of match highlights
+    pub fn find_spans(&self, text: &str) -> Vec<(usize, usize, &Colors)> {
+        if !self.is_enabled || self.level.is_some() {
+            return vec![];
+        }
+
+        if self.group_colors.iter().any(Option::is_some) {
+            return self.regex.regex.captures_iter(text)
+                .flat_map(|captures| {
+                    self.group_colors.iter()
+                        .enumerate()
+                        .filter_map(move |(idx, colors)| {
+                            let group = captures.get(idx + 1)?;
+                            Some((group.start(), group.end(), colors.as_ref()?))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .filter(|(start, end, _)| start < end)
+                .collect();
+        }
+
+        if self.scope != HighlightScope::Match {
+            return vec![];
         }
 
-        Err("Failed to create Highlight".into())
+        self.regex.regex.find_iter(text)
+            .filter(|found| !found.is_empty())
+            .map(|found| (found.start(), found.end(), &self.colors))
+            .collect()
+    }
+
+    pub fn get_level(&self) -> Option<LogLevel> {
+        self.level.as_ref().and_then(|level| level.parse().ok())
     }
-    pub fn is_matching(&self, line: &str) -> bool {
-        self.regex.regex.is_match(line)
+
+    pub fn set_level(&mut self, level: LogLevel) {
+        self.level = Some(level.name().to_string());
     }
 
     pub fn get_mut_colors(&mut self) -> &mut Colors {
@@ -104,12 +264,62 @@ impl Highlight {
         &mut self.pattern
     }
 
-    pub fn update_regex(&mut self) -> Result<(), ()> {
-        if let Ok(regex) = Regex::new(format!(r"(?i){}", self.pattern.as_str()).as_str()) {
-            self.regex = SerializableRegex::new(regex);
-            return Ok(());
+    pub fn get_is_enabled(&mut self) -> &mut bool {
+        &mut self.is_enabled
+    }
+
+    pub fn get_is_case_sensitive(&mut self) -> &mut bool {
+        &mut self.is_case_sensitive
+    }
+
+    pub fn get_is_whole_word(&mut self) -> &mut bool {
+        &mut self.is_whole_word
+    }
+
+    pub fn get_scope(&self) -> HighlightScope {
+        // Levels are told for whole lines
+        match self.level {
+            Some(_) => HighlightScope::Line,
+            None => self.scope,
         }
+    }
+
+    pub fn set_scope(&mut self, scope: HighlightScope) {
+        self.scope = scope;
+    }
 
-        Err(())
+    /// Colours of capture groups, one for every group of the pattern
+    pub fn get_group_colors(&mut self) -> &mut Vec<Option<Colors>> {
+        let group_count = match self.level {
+            Some(_) => 0,
+            None => self.regex.regex.captures_len() - 1,
+        };
+        self.group_colors.resize(group_count, None);
+        &mut self.group_colors
     }
-}
\ No newline at end of file
+
+    pub fn get_regex_error(&self) -> Option<&str> {
+        self.regex_error.as_deref()
+    }
+
+    /// Compile the pattern with case and whole word options. Invalid pattern keeps
+    /// the previous regex and is reported by `get_regex_error`.
+    pub fn update_regex(&mut self) {
+        let mut pattern = if self.is_whole_word {
+            format!(r"\b(?:{})\b", self.pattern)
+        } else {
+            self.pattern.clone()
+        };
+        if !self.is_case_sensitive {
+            pattern = format!("(?i){}", pattern);
+        }
+
+        match Regex::new(&pattern) {
+            Ok(regex) => {
+                self.regex = SerializableRegex::new(regex);
+                self.regex_error = None;
+            },
+            Err(error) => self.regex_error = Some(error.to_string()),
+        }
+    }
+}
@@ -1,84 +1,406 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, VecDeque};
+use std::ops::Range;
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
 use clipboard::{ClipboardContext, ClipboardProvider};
+use crate::bookmark::LineKey;
+use tailor::{parse_ansi, AnsiSpan, builtin_parser, detect_parser, LevelClassifier, LogLevel, Query, Record, RecordParser, RecordStart, TimestampParser, TimestampTracker};
 
+/// Lines received so far, up to the configured limits
+///
+/// Lines are identified by their number since the first line received, so indices stay
+/// valid when the oldest lines are dropped to keep the limits. Lines are kept without ANSI
+/// escape sequences, so that filter, search and copy see plain text, styles set by the
+/// sequences are kept aside.
 pub struct LinesState {
-    lines: Vec<String>,
-    filtered_lines: Vec<(String, u32)>,
-    /// (line, id)
-    selected_lines: HashSet<usize>,
+    lines: VecDeque<String>,
+    /// Parts of every kept line styled by ANSI escape sequences
+    line_styles: VecDeque<Vec<AnsiSpan>>,
+    /// Whether every kept line was written to standard error by followed command
+    line_is_error: VecDeque<bool>,
+    /// Number of the oldest lines dropped, i.e. index of the first kept line
+    dropped: usize,
+    /// Total length of kept lines in bytes
+    bytes: usize,
+    /// File the lines are read from when following a folder
+    source: Option<PathBuf>,
+    /// Exit code of followed command since it exited, none inside when killed by signal
+    exit_code: Option<Option<i32>>,
+    /// Byte offset of every kept line in its source
+    line_offsets: VecDeque<u64>,
+    /// Byte offset of the next line, when the source does not tell it
+    next_offset: u64,
+    /// Byte offsets of the next lines told by the source
+    next_offsets: VecDeque<u64>,
+    /// Most lines kept, 0 for no limit
+    max_lines: usize,
+    /// Most bytes kept, 0 for no limit
+    max_bytes: usize,
+    /// Indices of lines of records matching the filter, except hidden continuation lines
+    filtered_lines: VecDeque<usize>,
+    /// Filter the filtered lines were computed with
+    filter: String,
+    /// Number of lines already evaluated with the filter
+    filtered_until: usize,
+    /// First lines of records with any line matching the filter
+    matched_records: BTreeSet<usize>,
+    /// Incremented whenever filtered lines change
+    revision: u64,
+    /// Revision at which filtered lines were last computed from scratch
+    reset_revision: u64,
+    selected_lines: BTreeSet<usize>,
+    /// Filtered lines need full recompute, e.g. after lines were regrouped
     is_dirty: bool,
+    /// Parser for format detected from the first lines, if any
+    parser: Option<Box<dyn RecordParser>>,
+    is_format_detected: bool,
+    /// Record start pattern when multi-line records are grouped, empty to guess
+    grouping: Option<String>,
+    record_start: Option<RecordStart>,
+    /// Index of the first line of record, for every kept line
+    line_records: VecDeque<usize>,
+    /// First lines of records with hidden continuation lines
+    collapsed_records: BTreeSet<usize>,
+    /// Extra level words the classifier was built with
+    level_words: String,
+    levels: LevelClassifier,
+    /// Time of every line, lines without timestamp inherit it from the line above
+    line_times: VecDeque<Option<DateTime<Utc>>>,
+    timestamps: TimestampTracker,
+    /// Filter text and query parsed from it
+    query: Option<(String, Result<Query, String>)>,
 }
 
 impl LinesState {
     pub fn new() -> Self {
         Self {
-            lines: vec![],
-            filtered_lines: vec![],
-            selected_lines: HashSet::new(),
+            lines: VecDeque::new(),
+            line_styles: VecDeque::new(),
+            line_is_error: VecDeque::new(),
+            dropped: 0,
+            bytes: 0,
+            source: None,
+            exit_code: None,
+            line_offsets: VecDeque::new(),
+            next_offset: 0,
+            next_offsets: VecDeque::new(),
+            max_lines: 0,
+            max_bytes: 0,
+            filtered_lines: VecDeque::new(),
+            filter: String::new(),
+            filtered_until: 0,
+            matched_records: BTreeSet::new(),
+            revision: 0,
+            reset_revision: 0,
+            selected_lines: BTreeSet::new(),
             is_dirty: true,
+            parser: None,
+            is_format_detected: false,
+            grouping: None,
+            record_start: None,
+            line_records: VecDeque::new(),
+            collapsed_records: BTreeSet::new(),
+            level_words: String::new(),
+            levels: LevelClassifier::default(),
+            line_times: VecDeque::new(),
+            timestamps: TimestampTracker::new(TimestampParser::new()),
+            query: None,
         }
     }
 
-    pub fn add_lines(&mut self, lines: Vec<String>) {
-        self.lines.extend(lines);
-        self.is_dirty = true;
+    /// Add lines read from the source, error ones are written to standard error by followed command
+    pub fn add_lines(&mut self, lines: Vec<String>, is_error: bool) {
+        // Restarted command writes again
+        self.exit_code = None;
+        let first_new = self.get_end();
+        for line in lines {
+            let offset = self.next_offsets.pop_front().unwrap_or(self.next_offset);
+            self.line_offsets.push_back(offset);
+            self.next_offset = offset + line.len() as u64 + 1;
+            let (text, styles) = parse_ansi(line);
+            self.bytes += text.len();
+            self.lines.push_back(text);
+            self.line_styles.push_back(styles);
+            self.line_is_error.push_back(is_error);
+        }
+        self.next_offsets.clear();
+        if self.detect_format() {
+            // Records depend on the format, so earlier lines may match differently
+            self.group_lines(self.dropped);
+            self.is_dirty = true;
+        } else {
+            self.group_lines(first_new);
+        }
+        for idx in first_new..self.get_end() {
+            let record = self.get_record(idx);
+            let time = self.timestamps.push(&self.lines[idx - self.dropped], record.as_ref());
+            self.line_times.push_back(time);
+        }
+        self.drop_lines();
     }
 
     pub fn clear_lines(&mut self) {
         self.lines.clear();
+        self.line_styles.clear();
+        self.line_is_error.clear();
+        self.dropped = 0;
+        self.bytes = 0;
+        self.source = None;
+        self.line_offsets.clear();
+        self.next_offset = 0;
+        self.next_offsets.clear();
         self.selected_lines.clear();
         self.is_dirty = true;
+        self.parser = None;
+        self.is_format_detected = false;
+        self.line_records.clear();
+        self.collapsed_records.clear();
+        self.update_record_start();
+        self.line_times.clear();
+        self.timestamps.reset();
+    }
+
+    /// Tell byte offsets of the lines added next in their source, e.g. as counted by file reader
+    pub fn set_next_offsets(&mut self, offsets: Vec<u64>) {
+        self.next_offsets = offsets.into();
+    }
+
+    /// Tell which file of followed folder the lines come from, after the lines were cleared
+    pub fn set_source(&mut self, source: PathBuf) {
+        self.source = Some(source);
+    }
+
+    /// Tell that followed command exited, with its exit code
+    pub fn set_exit_code(&mut self, code: Option<i32>) {
+        self.exit_code = Some(code);
+    }
+
+    /// Exit status of followed command, none while it runs
+    pub fn get_exit_status(&self) -> Option<String> {
+        self.exit_code.map(|code| match code {
+            Some(code) => format!("Exited with code {}", code),
+            None => "Terminated".to_string(),
+        })
+    }
+
+    /// Identity of the line which stays the same when the lines are read again
+    pub fn get_line_key(&self, idx: usize) -> Option<LineKey> {
+        let offset = *self.line_offsets.get(idx.checked_sub(self.dropped)?)?;
+        Some(LineKey {
+            source: self.source.clone(),
+            offset,
+        })
+    }
+
+    /// Index of kept line with the identity
+    pub fn find_line(&self, key: &LineKey) -> Option<usize> {
+        if key.source != self.source {
+            return None;
+        }
+
+        let pos = self.line_offsets.binary_search(&key.offset).ok()?;
+        Some(self.dropped + pos)
+    }
+
+    /// Keep at most given number of lines and bytes, 0 for no limit. The oldest lines are dropped.
+    pub fn set_limits(&mut self, max_lines: usize, max_bytes: usize) {
+        if self.max_lines == max_lines && self.max_bytes == max_bytes {
+            return;
+        }
+
+        self.max_lines = max_lines;
+        self.max_bytes = max_bytes;
+        self.drop_lines();
+    }
+
+    /// Number of the oldest lines dropped to keep the limits
+    pub fn get_dropped_count(&self) -> usize {
+        self.dropped
+    }
+
+    /// Index after the last line
+    pub fn get_end(&self) -> usize {
+        self.dropped + self.lines.len()
+    }
+
+    /// Group lines of multi-line records, e.g. stack traces, by record start pattern.
+    /// Empty pattern lets the detected format or indentation tell where records start.
+    pub fn set_grouping(&mut self, grouping: Option<&str>) {
+        if self.grouping.as_deref() == grouping {
+            return;
+        }
+
+        self.grouping = grouping.map(String::from);
+        self.update_record_start();
+        self.collapsed_records.clear();
+        self.group_lines(self.dropped);
+        self.is_dirty = true;
+    }
+
+    /// Recognise extra level words, e.g. `CRIT=fatal, 35=warn`. Invalid words are ignored.
+    pub fn set_level_words(&mut self, level_words: &str) {
+        if self.level_words == level_words {
+            return;
+        }
+
+        self.level_words = level_words.to_string();
+        self.levels = LevelClassifier::default().with_levels(level_words).unwrap_or_else(|error| {
+            log::warn!("{}", error);
+            LevelClassifier::default()
+        });
+        self.query = None;
+        self.is_dirty = true;
+    }
+
+    /// Level of record the line belongs to
+    pub fn get_level(&self, idx: usize) -> Option<LogLevel> {
+        let start = self.get_record_range(idx).start;
+        match self.get_record(start) {
+            Some(record) => self.levels.record_level(&record),
+            None => self.levels.classify(self.get_line(start)?),
+        }
+    }
+
+    /// Time of the line, from its own timestamp or the closest line above with one
+    pub fn get_time(&self, idx: usize) -> Option<DateTime<Utc>> {
+        self.line_times.get(idx.checked_sub(self.dropped)?).copied().flatten()
+    }
+
+    /// Filtered line with time closest to the given one
+    pub fn find_filtered_line_at(&self, time: DateTime<Utc>) -> Option<usize> {
+        // Times do not decrease from line to line, only lines before the first timestamp have none
+        let next = self.filtered_lines.partition_point(|idx| self.get_time(*idx).is_none_or(|line_time| line_time < time));
+        let distance = |pos: usize| {
+            let idx = *self.filtered_lines.get(pos)?;
+            Some(((self.get_time(idx)? - time).num_milliseconds().abs(), idx))
+        };
+        [next.checked_sub(1).and_then(distance), distance(next)]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|(_, idx)| idx)
+    }
+
+    /// Why the filter text is not a valid query. Such filter is used as plain text.
+    pub fn get_query_error(&self) -> Option<&str> {
+        match &self.query {
+            Some((_, Err(error))) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Name of the log format detected from the first lines
+    pub fn get_format_name(&self) -> Option<&str> {
+        self.parser.as_ref().map(|parser| parser.name())
+    }
+
+    pub fn get_line(&self, idx: usize) -> Option<&String> {
+        self.lines.get(idx.checked_sub(self.dropped)?)
+    }
+
+    /// Parts of the line styled by ANSI escape sequences, by byte ranges of the line
+    pub fn get_line_styles(&self, idx: usize) -> &[AnsiSpan] {
+        idx.checked_sub(self.dropped)
+            .and_then(|idx| self.line_styles.get(idx))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether the line was written to standard error by followed command
+    pub fn is_error_line(&self, idx: usize) -> bool {
+        idx.checked_sub(self.dropped)
+            .and_then(|idx| self.line_is_error.get(idx))
+            .is_some_and(|is_error| *is_error)
+    }
+
+    /// Parse line with detected format
+    pub fn get_record(&self, idx: usize) -> Option<Record> {
+        let parser = self.parser.as_ref()?;
+        parser.parse(self.get_line(idx)?)
+    }
+
+    /// Line formatted as indented JSON, if it holds JSON object
+    pub fn get_pretty_json(&self, idx: usize) -> Option<String> {
+        let line = self.get_line(idx)?;
+        if !line.trim_start().starts_with('{') {
+            return None;
+        }
+
+        match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(value) if value.is_object() => serde_json::to_string_pretty(&value).ok(),
+            _ => None,
+        }
+    }
+
+    /// Indices of all lines of record the line belongs to
+    pub fn get_record_range(&self, idx: usize) -> Range<usize> {
+        match idx.checked_sub(self.dropped).and_then(|pos| self.line_records.get(pos)) {
+            Some(start) => *start..self.dropped + self.line_records.partition_point(|record| record <= start),
+            None => idx..idx + 1,
+        }
+    }
+
+    pub fn toggle_collapsed(&mut self, idx: usize) {
+        let start = self.get_record_range(idx).start;
+        if !self.collapsed_records.remove(&start) {
+            self.collapsed_records.insert(start);
+        }
+        self.is_dirty = true;
+    }
+
+    pub fn is_collapsed(&self, idx: usize) -> bool {
+        self.collapsed_records.contains(&self.get_record_range(idx).start)
     }
 
     pub fn toggle_single_line_selection(&mut self, idx: usize) {
+        let record = self.get_record_range(idx);
         if self.selected_lines.contains(&idx) {
-            if self.selected_lines.len() > 1 {
+            if self.selected_lines.len() > record.len() {
                 self.selected_lines.clear();
-                self.selected_lines.insert(idx);
+                self.selected_lines.extend(record);
             } else {
                 self.selected_lines.clear();
             }
         } else {
             self.selected_lines.clear();
-            self.selected_lines.insert(idx);
+            self.selected_lines.extend(record);
         }
     }
 
     pub fn toggle_add_selection(&mut self, idx: usize) {
+        let record = self.get_record_range(idx);
         if self.selected_lines.contains(&idx) {
-            self.selected_lines.remove(&idx);
+            for i in record {
+                self.selected_lines.remove(&i);
+            }
         } else {
-            self.selected_lines.insert(idx);
+            self.selected_lines.extend(record);
         }
     }
 
     pub fn toggle_add_range_selection(&mut self, idx: usize) {
+        let record = self.get_record_range(idx);
         if self.selected_lines.is_empty() {
-            self.selected_lines.insert(idx);
+            self.selected_lines.extend(record);
         } else {
-            let min_idx = if let Some(min) = self.selected_lines.iter().min() {
-                *min
-            } else {
-                idx
-            };
-            let max_idx = if let Some(max) = self.selected_lines.iter().max() {
-                *max
-            } else {
-                idx
-            };
+            let min_idx = self.selected_lines.first().copied().unwrap_or(idx);
+            let max_idx = self.selected_lines.last().copied().unwrap_or(idx);
             if idx < min_idx {
-                for i in idx..max_idx {
+                for i in record.start..max_idx {
                     self.selected_lines.insert(i);
                 }
             }
             if idx > max_idx {
-                for i in min_idx..=idx {
+                for i in min_idx..record.end {
                     self.selected_lines.insert(i);
                 }
             }
         }
     }
 
+    pub fn get_first_selected(&self) -> Option<usize> {
+        self.selected_lines.first().copied()
+    }
+
     pub fn is_selected(&self, idx: usize) -> bool {
         self.selected_lines.contains(&idx)
     }
@@ -92,34 +414,285 @@ impl LinesState {
         }
     }
 
-    pub fn get_filtered_lines(&mut self, pattern: &String) -> &Vec<(String, u32)> {
+    /// Update filtered lines for the filter, see `tailor::Query` for its syntax.
+    /// Only lines added since the last call are evaluated unless the filter changed.
+    pub fn filter_lines(&mut self, pattern: &str) {
+        if self.query.as_ref().is_none_or(|(text, _)| text != pattern) {
+            let query = Query::parse(pattern).map(|query| query.with_levels(self.levels.clone()));
+            self.query = Some((pattern.to_string(), query));
+        }
+        if self.filter != pattern {
+            self.filter = pattern.to_string();
+            self.is_dirty = true;
+        }
+
+        let is_reset = self.is_dirty;
         if self.is_dirty {
-            if pattern.is_empty() {
-                self.filtered_lines = self
-                    .lines
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, line)| (line.clone(), idx as u32))
-                    .collect();
-            } else {
-                self.filtered_lines = self
-                    .lines
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, line)| (line.clone(), idx as u32))
-                    .filter(|(line, _)| line.contains(pattern))
-                    .collect();
+            self.filtered_lines.clear();
+            self.matched_records.clear();
+            self.filtered_until = 0;
+            self.is_dirty = false;
+        } else if self.filtered_until == self.get_end() {
+            return;
+        }
+
+        // New lines may continue the last evaluated record, so it is evaluated again
+        let from = match self.filtered_until.max(self.dropped) {
+            until if until == self.dropped => until,
+            until => self.get_record_range(until - 1).start,
+        };
+        self.matched_records.remove(&from);
+        let kept = self.filtered_lines.partition_point(|idx| *idx < from);
+        self.filtered_lines.truncate(kept);
+
+        for idx in from..self.get_end() {
+            if pattern.is_empty() || self.is_matching(idx, &self.lines[idx - self.dropped], pattern) {
+                self.matched_records.insert(self.get_record_range(idx).start);
+            }
+        }
+        for idx in from..self.get_end() {
+            let start = self.get_record_range(idx).start;
+            let is_hidden = start != idx && self.collapsed_records.contains(&start);
+            if self.matched_records.contains(&start) && !is_hidden {
+                self.filtered_lines.push_back(idx);
             }
         }
+        self.filtered_until = self.get_end();
+        self.revision += 1;
+        if is_reset {
+            self.reset_revision = self.revision;
+        }
+    }
 
+    /// Indices of lines passing the filter given to `filter_lines`
+    pub fn get_filtered_lines(&self) -> &VecDeque<usize> {
         &self.filtered_lines
     }
 
+    /// Tells whether filtered lines changed since the revision was taken
+    pub fn get_revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Filtered lines taken before this revision are not kept in the current ones, other
+    /// revisions only drop the oldest lines and add or change lines of the last records
+    pub fn get_reset_revision(&self) -> u64 {
+        self.reset_revision
+    }
+
+    fn is_matching(&self, idx: usize, line: &str, pattern: &str) -> bool {
+        match &self.query {
+            Some((_, Ok(query))) => {
+                let record = if query.needs_record() { self.get_record(idx) } else { None };
+                query.matches(line, record.as_ref(), self.get_time(idx))
+            },
+            _ => line.contains(pattern),
+        }
+    }
+
+    /// Returns true when format got detected by this call
+    fn detect_format(&mut self) -> bool {
+        if self.is_format_detected {
+            return false;
+        }
+
+        self.parser = detect_parser(self.lines.make_contiguous());
+        // Keep trying until there are enough lines to tell the format for sure
+        self.is_format_detected = self.parser.is_some() || self.lines.len() >= tailor::DETECT_SAMPLE_LINES;
+        if self.parser.is_some() {
+            self.update_record_start();
+        }
+
+        self.parser.is_some()
+    }
+
+    fn update_record_start(&mut self) {
+        self.record_start = self.grouping.as_ref().map(|pattern| {
+            if !pattern.is_empty() {
+                match RecordStart::pattern(pattern) {
+                    Ok(record_start) => return record_start,
+                    Err(error) => log::warn!("{}", error),
+                }
+            }
+
+            match self.get_format_name().and_then(builtin_parser) {
+                Some(parser) => RecordStart::Format(parser),
+                None => RecordStart::Heuristic,
+            }
+        });
+    }
+
+    /// Assign lines starting from given index to records
+    fn group_lines(&mut self, from: usize) {
+        self.line_records.truncate(from - self.dropped);
+        for idx in from..self.get_end() {
+            let pos = idx - self.dropped;
+            let is_start = match &self.record_start {
+                Some(record_start) => pos == 0 || record_start.is_start(&self.lines[pos]),
+                None => true,
+            };
+            let record = if is_start { idx } else { self.line_records[pos - 1] };
+            self.line_records.push_back(record);
+        }
+    }
+
+    /// Drop the oldest lines exceeding the limits
+    fn drop_lines(&mut self) {
+        let dropped = self.dropped;
+        while self.lines.len() > 1 && ((self.max_lines > 0 && self.lines.len() > self.max_lines)
+            || (self.max_bytes > 0 && self.bytes > self.max_bytes)) {
+            if let Some(line) = self.lines.pop_front() {
+                self.bytes -= line.len();
+            }
+            self.line_styles.pop_front();
+            self.line_is_error.pop_front();
+            self.line_records.pop_front();
+            self.line_times.pop_front();
+            self.line_offsets.pop_front();
+            self.dropped += 1;
+        }
+        if self.dropped == dropped {
+            return;
+        }
+
+        // Remaining lines of partly dropped record form a record of their own
+        for record in self.line_records.iter_mut().take_while(|record| **record < self.dropped) {
+            *record = self.dropped;
+        }
+        let first = self.dropped;
+        self.selected_lines = self.selected_lines.split_off(&first);
+        self.collapsed_records = self.collapsed_records.split_off(&first);
+        self.matched_records = self.matched_records.split_off(&first);
+        let kept = self.filtered_lines.partition_point(|idx| *idx < first);
+        self.filtered_lines.drain(..kept);
+        self.revision += 1;
+    }
+
     fn get_selected_text(&self) -> String {
         self.selected_lines
             .iter()
-            .map(|idx| self.lines[*idx].clone())
+            .filter_map(|idx| self.get_line(*idx).cloned())
             .collect::<Vec<String>>()
             .join("\n")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::lines::LinesState;
+
+    fn add_lines(lines: &mut LinesState, texts: &[&str]) {
+        lines.add_lines(texts.iter().map(|text| text.to_string()).collect(), false);
+    }
+
+    fn filtered(lines: &LinesState) -> Vec<usize> {
+        lines.get_filtered_lines().iter().copied().collect()
+    }
+
+    #[test]
+    fn appended_lines_filtered() {
+        let mut lines = LinesState::new();
+        add_lines(&mut lines, &["ERROR disk full", "INFO started"]);
+        lines.filter_lines("error");
+        assert_eq!(filtered(&lines), vec![0]);
+        let reset_revision = lines.get_reset_revision();
+        assert_eq!(reset_revision, lines.get_revision());
+
+        add_lines(&mut lines, &["INFO retrying", "ERROR disk still full"]);
+        lines.filter_lines("error");
+        assert_eq!(filtered(&lines), vec![0, 3]);
+        assert_eq!(lines.get_reset_revision(), reset_revision);
+        assert!(lines.get_revision() > reset_revision);
+
+        // Nothing new to evaluate
+        let revision = lines.get_revision();
+        lines.filter_lines("error");
+        assert_eq!(lines.get_revision(), revision);
+    }
+
+    #[test]
+    fn changed_filter_resets_lines() {
+        let mut lines = LinesState::new();
+        add_lines(&mut lines, &["ERROR disk full", "INFO started", "ERROR disk still full"]);
+        lines.filter_lines("error");
+        assert_eq!(filtered(&lines), vec![0, 2]);
+
+        lines.filter_lines("info");
+        assert_eq!(filtered(&lines), vec![1]);
+        assert_eq!(lines.get_reset_revision(), lines.get_revision());
+
+        lines.filter_lines("");
+        assert_eq!(filtered(&lines), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn evicted_lines_leave_filter() {
+        let mut lines = LinesState::new();
+        lines.set_limits(3, 0);
+        add_lines(&mut lines, &["ERROR disk full", "INFO started", "ERROR disk still full"]);
+        lines.filter_lines("error");
+        assert_eq!(filtered(&lines), vec![0, 2]);
+        let reset_revision = lines.get_reset_revision();
+
+        add_lines(&mut lines, &["INFO retrying", "ERROR out of memory"]);
+        assert_eq!(filtered(&lines), vec![2]);
+        lines.filter_lines("error");
+        assert_eq!(filtered(&lines), vec![2, 4]);
+        // Dropping the oldest lines keeps the other filtered lines
+        assert_eq!(lines.get_reset_revision(), reset_revision);
+    }
+
+    #[test]
+    fn dropped_lines_counted() {
+        let mut lines = LinesState::new();
+        lines.set_limits(2, 0);
+        add_lines(&mut lines, &["first", "second", "third", "fourth", "fifth"]);
+        assert_eq!(lines.get_dropped_count(), 3);
+        assert_eq!(lines.get_end(), 5);
+        assert_eq!(lines.get_line(2), None);
+        assert_eq!(lines.get_line(3).map(String::as_str), Some("fourth"));
+
+        // Lowering the limit drops lines right away
+        lines.set_limits(1, 0);
+        assert_eq!(lines.get_dropped_count(), 4);
+        assert_eq!(lines.get_line(4).map(String::as_str), Some("fifth"));
+    }
+
+    #[test]
+    fn dropped_lines_unselected() {
+        let mut lines = LinesState::new();
+        add_lines(&mut lines, &["first", "second", "third", "fourth", "fifth"]);
+        lines.toggle_add_selection(1);
+        lines.toggle_add_selection(3);
+        lines.set_limits(3, 0);
+        assert!(!lines.is_selected(1));
+        assert!(lines.is_selected(3));
+        assert_eq!(lines.get_first_selected(), Some(3));
+    }
+
+    #[test]
+    fn partly_dropped_record_kept() {
+        let mut lines = LinesState::new();
+        lines.set_grouping(Some(""));
+        add_lines(&mut lines, &["Exception in main", "    at first", "    at second", "INFO recovered"]);
+        assert_eq!(lines.get_record_range(2), 0..3);
+
+        lines.set_limits(3, 0);
+        assert_eq!(lines.get_record_range(1), 1..3);
+        assert_eq!(lines.get_record_range(2), 1..3);
+        assert_eq!(lines.get_record_range(3), 3..4);
+    }
+
+    #[test]
+    fn size_limit_kept() {
+        let mut lines = LinesState::new();
+        lines.set_limits(0, 10);
+        add_lines(&mut lines, &["12345", "12345", "12345"]);
+        assert_eq!(lines.get_dropped_count(), 1);
+
+        // The last line is kept even when over the limit
+        add_lines(&mut lines, &["12345678901234567890"]);
+        assert_eq!(lines.get_dropped_count(), 3);
+        assert_eq!(lines.get_end(), 4);
+    }
+}
@@ -1,9 +1,16 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+mod bookmark;
 mod highlight;
 mod lines;
 mod panels;
+mod persist;
+mod profile;
+mod search;
 mod session;
+mod settings;
+mod source;
+mod tab;
 mod widgets;
 mod windows;
 
@@ -14,131 +21,216 @@ const APP_INFO: AppInfo = AppInfo {
     author: "Alexander Devaikin",
 };
 
-use crate::lines::LinesState;
-use crate::panels::main::MainPanel;
-use crate::session::Session;
+use crate::profile::Profiles;
+use crate::settings::Settings;
+use crate::source::Source;
+use crate::tab::Tab;
 use crate::widgets::recents::RecentsBox;
+use chrono::{DateTime, Utc};
 use eframe::{egui, App, Frame};
-use egui::{Align, Button, Context, Layout, TextEdit, TopBottomPanel};
-use regex::Regex;
-use std::path::{Path, PathBuf};
+use egui::{Align, Button, CentralPanel, Context, Layout, Rect, TopBottomPanel, Ui, Vec2};
+use std::path::PathBuf;
 use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::{Arc, Mutex};
-use tailor::{Message, Tailor};
+use tailor::Tailor;
 use windows::Windows;
 
-struct TailorClient {
-    #[allow(dead_code)]
-    handle: std::thread::JoinHandle<()>,
-}
+/// Space between split panes
+const PANE_SPACING: f32 = 4.0;
 
-impl TailorClient {
-    fn new(
-        tailor: &mut Tailor,
-        path: &Path,
-        ctx: Context,
-        log_contents: Arc<Mutex<LinesState>>,
-    ) -> Self {
-        let (message_tx, message_rx) = channel();
-        let client_handle = std::thread::spawn(move || {
-            while match message_rx.recv_timeout(std::time::Duration::from_secs(2)) {
-                Ok(msg) => {
-                    if let Ok(mut lines) = log_contents.lock() {
-                        match msg {
-                            Message::NewLines(recv_lines) => {
-                                (*lines).add_lines(recv_lines);
-                            }
-                            Message::NewFile(_path) => {
-                                (*lines).clear_lines();
-                            }
-                        }
-                    }
+/// Another tab shown next to the active one
+#[derive(Clone, Copy)]
+struct Split {
+    tab: usize,
+    /// Panes are above each other rather than side by side
+    is_vertical: bool,
+    /// Scroll the other pane to lines with time closest to the top line of the active one
+    sync_by_time: bool,
+}
 
-                    ctx.request_repaint();
-                    true
-                }
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => true,
-                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
-                    log::error!("Tailor thread disconnected");
-                    false
-                }
-            } {}
-        });
+/// Two distinct tabs borrowed at once
+fn get_pair_mut(tabs: &mut [Tab], first: usize, second: usize) -> Option<(&mut Tab, &mut Tab)> {
+    if first == second || first.max(second) >= tabs.len() {
+        return None;
+    }
 
-        tailor.watch(PathBuf::from(path), message_tx);
+    if first < second {
+        let (head, tail) = tabs.split_at_mut(second);
+        Some((&mut head[first], &mut tail[0]))
+    } else {
+        let (head, tail) = tabs.split_at_mut(first);
+        Some((&mut tail[0], &mut head[second]))
+    }
+}
 
-        Self {
-            handle: client_handle,
-        }
+/// Split the rect in two halves with spacing between them
+fn split_rect(rect: Rect, is_vertical: bool) -> (Rect, Rect) {
+    if is_vertical {
+        let half = (rect.height() - PANE_SPACING) / 2.0;
+        (Rect::from_min_size(rect.min, Vec2::new(rect.width(), half)),
+         Rect::from_min_size(rect.min + Vec2::new(0.0, half + PANE_SPACING), Vec2::new(rect.width(), half)))
+    } else {
+        let half = (rect.width() - PANE_SPACING) / 2.0;
+        (Rect::from_min_size(rect.min, Vec2::new(half, rect.height())),
+         Rect::from_min_size(rect.min + Vec2::new(half + PANE_SPACING, 0.0), Vec2::new(half, rect.height())))
     }
 }
 
 struct TailorApp {
     windows: Windows,
-    session: Session,
     file_pick_channel: (Sender<PathBuf>, Receiver<PathBuf>),
-    is_dirty: bool,
     recents_box: RecentsBox,
-    next_open_file: Option<PathBuf>,
+    /// Sources to open in new tabs in the next frame
+    open_sources: Vec<Source>,
     tailor: Tailor,
-    tailor_client: Option<TailorClient>,
-    log_contents: Arc<Mutex<LinesState>>,
-    log_panel: MainPanel,
+    /// Highlight profiles shared by sessions
+    profiles: Profiles,
+    settings: Settings,
+    tabs: Vec<Tab>,
+    active_tab: usize,
+    split: Option<Split>,
+    /// Time the split panes were last aligned to
+    synced_time: Option<DateTime<Utc>>,
     settings_panel: panels::session_settings::SessionSettingsPanel,
-    filter_text: String,
-    search_text: String,
-    search_regex: Option<Regex>,
+    bookmarks_panel: panels::bookmarks::BookmarksPanel,
 }
 
 impl TailorApp {
-    fn new(tailor: Tailor) -> Self {
+    fn new(tailor: Tailor, open_source: Option<Source>) -> Self {
+        let mut open_sources = tab::try_load_open_tabs();
+        open_sources.extend(open_source);
         Self {
             windows: Windows::default(),
-            session: Session::default(),
             file_pick_channel: channel(),
-            is_dirty: true,
             recents_box: RecentsBox::default(),
-            next_open_file: None,
+            open_sources,
             tailor,
-            tailor_client: None,
-            log_contents: Arc::new(Mutex::new(LinesState::new())),
-            log_panel: MainPanel::new(),
+            profiles: Profiles::try_load(),
+            settings: Settings::try_load(),
+            tabs: vec![],
+            active_tab: 0,
+            split: None,
+            synced_time: None,
             settings_panel: panels::session_settings::SessionSettingsPanel::default(),
-            filter_text: String::new(),
-            search_text: String::new(),
-            search_regex: None,
+            bookmarks_panel: panels::bookmarks::BookmarksPanel::default(),
+        }
+    }
+
+    /// Switch to tab following the source, opening new one unless there is such
+    fn open_tab(&mut self, source: Source, ctx: &Context) {
+        match self.tabs.iter().position(|tab| *tab.get_source() == source) {
+            Some(idx) => self.activate_tab(idx),
+            None => {
+                let tab = Tab::open(&mut self.tailor, &source, self.windows.command.get_restart_on_exit(), &self.profiles, &self.settings, ctx);
+                self.tabs.push(tab);
+                self.active_tab = self.tabs.len() - 1;
+                tab::try_save_open_tabs(&self.tabs);
+            }
+        }
+
+        if source == Source::Stdin {
+            self.recents_box.clear_selected_recent();
+        } else {
+            self.recents_box.update_recents(&source, self.settings.max_recents);
+        }
+    }
+
+    /// Make the tab active. Tab shown in split pane swaps places with the active one.
+    fn activate_tab(&mut self, idx: usize) {
+        if let Some(split) = &mut self.split {
+            if split.tab == idx {
+                split.tab = self.active_tab;
+            }
+        }
+        self.active_tab = idx;
+    }
+
+    /// Show the tab next to the active one
+    fn split_with(&mut self, idx: usize, is_vertical: bool) {
+        if idx == self.active_tab || idx >= self.tabs.len() {
+            return;
+        }
+
+        let sync_by_time = self.split.is_some_and(|split| split.sync_by_time);
+        self.split = Some(Split { tab: idx, is_vertical, sync_by_time });
+        self.synced_time = None;
+    }
+
+    /// Stop following source of the tab and forget it
+    fn close_tab(&mut self, idx: usize) {
+        let tab = self.tabs.remove(idx);
+        tab.close(&mut self.tailor);
+        if self.active_tab > idx || self.active_tab == self.tabs.len() {
+            self.active_tab = self.active_tab.saturating_sub(1);
+        }
+        if let Some(split) = &mut self.split {
+            if split.tab == idx {
+                self.split = None;
+            } else {
+                if split.tab > idx {
+                    split.tab -= 1;
+                }
+                if split.tab == self.active_tab || split.tab >= self.tabs.len() {
+                    self.split = None;
+                }
+            }
         }
+        tab::try_save_open_tabs(&self.tabs);
+    }
+
+    /// Draw the active tab, and the split one next to it
+    fn draw_panes(&mut self, ui: &mut Ui) {
+        let split = self.split;
+        let Some(split) = split else {
+            if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                tab.draw(ui, true, &self.profiles, &self.settings);
+            }
+            return;
+        };
+        let Some((active, other)) = get_pair_mut(&mut self.tabs, self.active_tab, split.tab) else {
+            return;
+        };
+
+        let (active_rect, other_rect) = split_rect(ui.available_rect_before_wrap(), split.is_vertical);
+        ui.allocate_ui_at_rect(active_rect, |ui| {
+            ui.push_id("active_pane", |ui| active.draw(ui, true, &self.profiles, &self.settings));
+        });
+        if split.sync_by_time {
+            let time = active.get_top_time();
+            if time != self.synced_time {
+                if let Some(time) = time {
+                    other.align_to_time(time);
+                }
+                self.synced_time = time;
+            }
+        }
+        ui.allocate_ui_at_rect(other_rect, |ui| {
+            ui.push_id("split_pane", |ui| other.draw(ui, false, &self.profiles, &self.settings));
+        });
     }
 }
 
 impl App for TailorApp {
-    fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
+    fn update(&mut self, ctx: &Context, frame: &mut Frame) {
+        let visuals = self.settings.get_visuals(frame.info().system_theme);
+        if ctx.style().visuals != visuals {
+            ctx.set_visuals(visuals);
+        }
+
         if let Ok(path) = self.file_pick_channel.1.try_recv() {
-            self.next_open_file = Some(path);
-            self.is_dirty = true;
+            self.open_sources.push(Source::File(path));
         }
 
-        if self.is_dirty {
-            if let Some(path) = &self.next_open_file {
-                if let Ok(mut lines) = self.log_contents.lock() {
-                    (*lines).clear_lines();
-                }
-                self.tailor_client = Some(TailorClient::new(
-                    &mut self.tailor,
-                    path,
-                    ctx.clone(),
-                    self.log_contents.clone(),
-                ));
-                self.session = Session::new(path.clone());
-                self.recents_box.update_recents(path.as_path());
-            }
+        if let Some(command) = self.windows.command.take_picked_command() {
+            self.open_sources.push(Source::Command(command));
+        }
 
-            self.is_dirty = false;
+        let active_source = self.tabs.get(self.active_tab).map(|tab| tab.get_source().clone());
+        if self.recents_box.is_dirty(active_source.as_ref()) {
+            self.open_sources.extend(self.recents_box.get_selected_recent());
         }
 
-        if self.recents_box.is_dirty(self.session.get_path().as_path()) {
-            self.next_open_file = Some(self.recents_box.get_selected_recent_path());
-            self.is_dirty = true;
+        for source in std::mem::take(&mut self.open_sources) {
+            self.open_tab(source, ctx);
         }
 
         TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -169,22 +261,46 @@ impl App for TailorApp {
                             }
                         }));
                     }
+                    let command_button =
+                        Button::new("⚙ Command…").selected(self.windows.command.get_is_visible());
+                    if ui.add(command_button)
+                        .on_hover_text("Run command and follow its output")
+                        .clicked() {
+                        self.windows.command.toggle_is_visible();
+                    }
                 });
 
                 ui.horizontal(|ui| {
                     self.recents_box.draw(ui);
                     let session_settings_button =
                         Button::new("🎨").selected(self.settings_panel.get_is_visible());
-                    if ui.button("☜")
+                    let active_path = active_source.as_ref().and_then(Source::get_path);
+                    if ui.add_enabled(active_path.is_some(), Button::new("☜"))
                         .on_hover_text("Reveal in file manager")
                         .clicked() {
-                        let _ = open::that(self.session.get_path());
+                        if let Some(path) = active_path {
+                            let _ = open::that(path);
+                        }
                     }
                     if ui.add(session_settings_button)
                         .on_hover_text("Session settings")
                         .clicked() {
                         self.settings_panel.toggle_is_visible();
                     }
+                    let profiles_button =
+                        Button::new("📚").selected(self.windows.profiles.get_is_visible());
+                    if ui.add(profiles_button)
+                        .on_hover_text("Highlight profiles shared by sessions")
+                        .clicked() {
+                        self.windows.profiles.toggle_is_visible();
+                    }
+                    let bookmarks_button =
+                        Button::new("🔖").selected(self.bookmarks_panel.get_is_visible());
+                    if ui.add(bookmarks_button)
+                        .on_hover_text("Bookmarks")
+                        .clicked() {
+                        self.bookmarks_panel.toggle_is_visible();
+                    }
                 });
 
                 ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
@@ -193,55 +309,110 @@ impl App for TailorApp {
                     if ui.add(about_button).clicked() {
                         self.windows.about.toggle_is_visible();
                     }
+                    let preferences_button =
+                        Button::new("⚙ Preferences").selected(self.windows.preferences.get_is_visible());
+                    if ui.add(preferences_button).clicked() {
+                        self.windows.preferences.toggle_is_visible();
+                    }
                 });
             });
         });
 
-        if let Ok(mut log_contents) = self.log_contents.lock() {
-            self.log_panel.draw(
-                &mut self.session,
-                ctx,
-                &mut log_contents,
-                &self.filter_text,
-                &self.search_regex,
-            );
+        let mut activate_tab = None;
+        let mut close_tab = None;
+        let mut split_with = None;
+        let mut unsplit = false;
+        TopBottomPanel::top("tabs_panel").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (idx, tab) in self.tabs.iter().enumerate() {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    let is_split = self.split.is_some_and(|split| split.tab == idx);
+                    let title = tab.get_source().get_title();
+                    let title = if is_split { format!("◫ {}", title) } else { title };
+                    let tab_label = ui.selectable_label(idx == self.active_tab, title)
+                        .on_hover_text(tab.get_source().to_string());
+                    if tab_label.clicked() {
+                        activate_tab = Some(idx);
+                    }
+                    if tab_label.middle_clicked() {
+                        close_tab = Some(idx);
+                    }
+                    if idx != self.active_tab {
+                        tab_label.context_menu(|ui| {
+                            if ui.button("Show side by side").clicked() {
+                                split_with = Some((idx, false));
+                                ui.close_menu();
+                            }
+                            if ui.button("Show below").clicked() {
+                                split_with = Some((idx, true));
+                                ui.close_menu();
+                            }
+                        });
+                    }
+                    if ui.small_button("✖").on_hover_text("Close tab").clicked() {
+                        close_tab = Some(idx);
+                    }
+                    ui.add_space(8.0);
+                }
+
+                if let Some(split) = &mut self.split {
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        unsplit = ui.button("✖ Unsplit").clicked();
+                        let orientation = if split.is_vertical { "⬍ Stacked" } else { "⬌ Side by side" };
+                        if ui.button(orientation).on_hover_text("Change how panes are placed").clicked() {
+                            split.is_vertical = !split.is_vertical;
+                        }
+                        if ui.toggle_value(&mut split.sync_by_time, "⏱ Sync by time")
+                            .on_hover_text("Scroll the other pane to lines with time closest to the top line of the active one")
+                            .changed() {
+                            self.synced_time = None;
+                        }
+                    });
+                } else if self.tabs.len() > 1 {
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if ui.button("◫ Split").on_hover_text("Show the next tab side by side, right click tabs for more").clicked() {
+                            split_with = Some(((self.active_tab + 1) % self.tabs.len(), false));
+                        }
+                    });
+                }
+            });
+        });
+        if unsplit {
+            self.split = None;
+        }
+        if let Some(idx) = activate_tab {
+            self.activate_tab(idx);
+        }
+        if let Some((idx, is_vertical)) = split_with {
+            self.split_with(idx, is_vertical);
+        }
+        if let Some(idx) = close_tab {
+            self.close_tab(idx);
+        }
+
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            self.settings_panel.draw(ctx, tab.get_session(), &self.profiles);
+            self.bookmarks_panel.draw(ctx, tab);
         }
-        self.settings_panel.draw(ctx, &mut self.session);
-        self.windows.draw(ctx);
+        self.windows.draw(ctx, &mut self.profiles, &mut self.settings);
 
         TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.horizontal(|ui| {
-                    ui.label(self.session.get_path().display().to_string());
-                });
+                if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                    tab.draw_status(ui, ctx);
+                }
+            });
+        });
 
-                ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
-                    if ui
-                        .add(
-                            TextEdit::singleline(&mut self.search_text)
-                                .hint_text("Search")
-                                .desired_width(120.0),
-                        )
-                        .changed()
-                    {
-                        if !self.search_text.is_empty() {
-                            if let Ok(regex) =
-                                Regex::new(format!(r"(?i){}", &self.search_text).as_str())
-                            {
-                                self.search_regex = Some(regex);
-                            }
-                        } else {
-                            self.search_regex = None;
-                        }
-                    }
-                    ui.add(
-                        TextEdit::singleline(&mut self.filter_text)
-                            .hint_text("Filter")
-                            .desired_width(120.0),
-                    );
+        if self.tabs.is_empty() {
+            CentralPanel::default().show(ctx, |ui| {
+                ui.centered_and_justified(|ui| {
+                    ui.label("Open file, folder or command to follow it in a new tab");
                 });
             });
-        });
+        } else {
+            CentralPanel::default().frame(egui::Frame::none()).show(ctx, |ui| self.draw_panes(ui));
+        }
     }
 }
 
@@ -275,13 +446,14 @@ fn main() {
     // log::info!("Menu: {:?}", menu);
     // let _: () = unsafe { msg_send![ns_app, setMainMenu: menu] };
 
+    let open_source = std::env::args().nth(1).map(|arg| Source::from_arg(&arg));
     match Tailor::new() {
         Ok(tailor) => {
             let native_options = eframe::NativeOptions::default();
             let _ = eframe::run_native(
                 "Tailor",
                 native_options,
-                Box::new(|_cc| Box::new(TailorApp::new(tailor))),
+                Box::new(|_cc| Box::new(TailorApp::new(tailor, open_source))),
             );
         }
         Err(msg) => {
@@ -0,0 +1,85 @@
+use egui::{Context, Label, TextEdit};
+use crate::tab::Tab;
+
+/// Lines around bookmarked ones included in export
+const EXPORT_CONTEXT_LINES: usize = 3;
+
+#[derive(Default)]
+pub struct BookmarksPanel {
+    is_visible: bool,
+}
+
+impl BookmarksPanel {
+    pub fn toggle_is_visible(&mut self) {
+        self.is_visible = !self.is_visible;
+    }
+
+    pub fn get_is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    pub fn draw(&mut self, ctx: &Context, tab: &mut Tab) {
+        if !self.is_visible {
+            return;
+        }
+
+        egui::SidePanel::left("bookmarks")
+            .resizable(true)
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.heading("Bookmarks");
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("▲").on_hover_text("Previous bookmark (Shift+F2)").clicked() {
+                        tab.go_to_bookmark(false);
+                    }
+                    if ui.button("▼").on_hover_text("Next bookmark (F2)").clicked() {
+                        tab.go_to_bookmark(true);
+                    }
+                    if ui.button("Export…")
+                        .on_hover_text(format!("Save bookmarks with {} lines around them", EXPORT_CONTEXT_LINES))
+                        .clicked() {
+                        let text = tab.export_bookmarks(EXPORT_CONTEXT_LINES);
+                        let task = rfd::AsyncFileDialog::new().set_file_name("bookmarks.txt").save_file();
+                        std::thread::spawn(move || futures::executor::block_on(async move {
+                            if let Some(file) = task.await {
+                                if let Err(error) = std::fs::write(file.path(), text) {
+                                    log::error!("Failed to export bookmarks: {}", error);
+                                }
+                            }
+                        }));
+                    }
+                });
+                ui.separator();
+
+                let mut jump_to = None;
+                let mut remove_at = None;
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (index, bookmark) in tab.get_session().get_bookmarks().iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.button("🗑").clicked() {
+                                remove_at = Some(index);
+                            }
+                            if ui.button("→").on_hover_text("Show the line").clicked() {
+                                jump_to = Some(bookmark.get_key().clone());
+                            }
+                            ui.add(Label::new(bookmark.get_text()).truncate(true))
+                                .on_hover_text(bookmark.get_text());
+                        });
+                        ui.add(TextEdit::singleline(bookmark.get_note())
+                            .hint_text("Note")
+                            .desired_width(f32::INFINITY));
+                    }
+                });
+
+                if let Some(key) = jump_to {
+                    tab.scroll_to_key(&key);
+                }
+                if let Some(index) = remove_at {
+                    tab.get_session().get_bookmarks().remove(index);
+                    tab.get_session().save();
+                }
+            });
+    }
+}
@@ -1,48 +1,233 @@
-use egui::{CentralPanel, Color32, Context, FontId, Label, Sense, TextFormat};
+use std::collections::{HashSet, VecDeque};
+use egui::{Color32, FontId, Label, RichText, Sense, Stroke, TextFormat, Ui};
 use egui::text::{LayoutJob, LayoutSection};
 use regex::Regex;
+use tailor::{AnsiColor, LogLevel, Record};
+use crate::highlight::{color32_to_rgb, Colors};
 use crate::lines::LinesState;
+use crate::profile::Profile;
+use crate::search::Search;
 use crate::session::Session;
+use crate::settings::Settings;
+
+/// Rows kept above the line scrolled to
+const SCROLL_MARGIN_ROWS: usize = 5;
 
 fn find_ranges(line: &str, regex: &Regex) -> Vec<(usize, usize)> {
     let captures = regex.find_iter(line);
     captures.map(|c| (c.start(), c.end())).collect()
 }
 
-fn fill_empty_ranges(ranges: Vec<(usize, usize)>, total_len: usize) -> Vec<(usize, usize, bool)> {
-    let mut result = vec![];
-    let mut last = 0;
-    for (start, end) in ranges {
-        if start > last {
-            result.push((last, start, false));
-        }
-        result.push((start, end, true));
-        last = end;
-    }
-    if total_len > last {
-        result.push((last, total_len, false));
+/// Split text into sections formatted by the last layer covering them, or by the base format
+fn layer_sections(total_len: usize, base: &TextFormat, layers: &[(usize, usize, TextFormat)]) -> Vec<LayoutSection> {
+    let mut bounds: Vec<usize> = layers.iter()
+        .flat_map(|(start, end, _)| [*start, *end])
+        .chain([0, total_len])
+        .filter(|bound| *bound <= total_len)
+        .collect();
+    bounds.sort();
+    bounds.dedup();
+    bounds.windows(2)
+        .map(|range| {
+            let format = layers.iter()
+                .rev()
+                .find(|(start, end, _)| *start <= range[0] && range[1] <= *end)
+                .map_or(base, |(_, _, format)| format);
+            LayoutSection {
+                leading_space: 0.0,
+                byte_range: range[0]..range[1],
+                format: format.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Row shown in the panel
+enum Row {
+    /// Index of line
+    Line(usize),
+    /// One line of expanded JSON of the line above
+    Details(String),
+    /// Marker of the oldest lines dropped to keep the limits
+    Dropped(usize),
+}
+
+fn ansi_to_rgb(color: AnsiColor) -> [f32; 3] {
+    let (r, g, b) = color.to_rgb();
+    [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0]
+}
+
+fn toggle_bookmark(session: &mut Session, log_contents: &LinesState, line_id: usize) {
+    if let (Some(key), Some(line)) = (log_contents.get_line_key(line_id), log_contents.get_line(line_id)) {
+        session.toggle_bookmark(key, line);
     }
-    result
 }
 
-pub struct MainPanel {
+fn compact_line(record: &Record, level: Option<LogLevel>) -> String {
+    [record.timestamp.as_deref(), level.map(|level| level.name()).or(record.level.as_deref()), Some(record.message.as_str())]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
 
+pub struct MainPanel {
+    /// Ids of lines with expanded JSON details
+    expanded_lines: HashSet<usize>,
+    rows: VecDeque<Row>,
+    /// Revision of filtered lines the rows were built from, none to rebuild them
+    rows_revision: Option<u64>,
+    /// Keep the last lines in view as new lines come
+    is_following: bool,
+    /// Id of line to bring into view in the next frame and number of rows to keep above it
+    scroll_to_line: Option<(usize, usize)>,
+    /// Id of the first line in view
+    top_line: Option<usize>,
+    /// Bring the last lines into view in the next frame
+    scroll_to_end: bool,
+    font_size: f32,
+    /// Space between rows, which are as high as the font
+    line_spacing: f32,
+    /// Keep highlights readable on session background, see `Colors::adjusted_to`
+    adjust_contrast: bool,
 }
 
 impl MainPanel {
     pub fn new() -> Self {
         Self {
+            expanded_lines: HashSet::new(),
+            rows: VecDeque::new(),
+            rows_revision: None,
+            is_following: true,
+            scroll_to_line: None,
+            top_line: None,
+            scroll_to_end: false,
+            font_size: 12.0,
+            line_spacing: 3.0,
+            adjust_contrast: true,
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.font_size = settings.font_size;
+        self.line_spacing = settings.line_spacing;
+        self.adjust_contrast = settings.adjust_contrast;
+    }
 
+    /// Highlight colours, adjusted to the session background if asked to
+    fn get_readable_colors(&self, colors: &Colors, session_colors: &Colors) -> Colors {
+        if self.adjust_contrast {
+            colors.adjusted_to(session_colors.background)
+        } else {
+            colors.clone()
         }
     }
 
-    pub fn draw(&mut self, session: &mut Session, ctx: &Context, log_contents: &mut LinesState,
-        filter_text: &String, search_pattern: &Option<Regex>) {
-        ctx.input(|i| {
-            if i.key_pressed(egui::Key::C) && (i.modifiers.command || i.modifiers.ctrl) {
-                log_contents.copy_selected_to_clipboard();
-            }
+    /// Bring the line into view and stop following new lines
+    pub fn scroll_to_line(&mut self, line_id: usize) {
+        self.scroll_to_line = Some((line_id, SCROLL_MARGIN_ROWS));
+        self.is_following = false;
+    }
+
+    /// Bring the line to the top of the view and stop following new lines
+    pub fn align_line(&mut self, line_id: usize) {
+        self.scroll_to_line = Some((line_id, 0));
+        self.is_following = false;
+    }
+
+    /// Id of the first line in view
+    pub fn get_top_line(&self) -> Option<usize> {
+        self.top_line
+    }
+
+    /// Bring the last lines into view and keep them in view as new lines come
+    pub fn follow(&mut self) {
+        self.scroll_to_line = None;
+        self.scroll_to_end = true;
+        self.is_following = true;
+    }
+
+    pub fn get_is_following(&self) -> bool {
+        self.is_following
+    }
+
+    /// Update rows for filtered lines changed since the rows were built. Rows of dropped lines
+    /// are removed and rows of new ones added, all rows are built again only when filtered lines
+    /// were computed from scratch.
+    fn update_rows(&mut self, log_contents: &LinesState) {
+        if self.rows_revision == Some(log_contents.get_revision()) {
+            return;
+        }
+        if self.rows_revision.is_none_or(|revision| revision < log_contents.get_reset_revision()) {
+            self.rows.clear();
+        }
+
+        let dropped = log_contents.get_dropped_count();
+        if matches!(self.rows.front(), Some(Row::Dropped(_))) {
+            self.rows.pop_front();
+        }
+        while let Some(row) = self.rows.front() {
+            match row {
+                Row::Line(line_id) if *line_id >= dropped => break,
+                _ => self.rows.pop_front(),
+            };
+        }
+
+        // Lines of the last record may have been evaluated again, so its rows are built again
+        let last_line = self.rows.iter().rev().find_map(|row| match row {
+            Row::Line(line_id) => Some(*line_id),
+            _ => None,
         });
+        let from = last_line.map_or(dropped, |line_id| log_contents.get_record_range(line_id).start.max(dropped));
+        let mut kept = self.rows.len();
+        for (pos, row) in self.rows.iter().enumerate().rev() {
+            match row {
+                Row::Line(line_id) if *line_id < from => break,
+                Row::Line(_) => kept = pos,
+                _ => (),
+            }
+        }
+        self.rows.truncate(kept);
+
+        let filtered_lines = log_contents.get_filtered_lines();
+        let first_new = filtered_lines.partition_point(|line_id| *line_id < from);
+        for line_id in filtered_lines.range(first_new..) {
+            self.rows.push_back(Row::Line(*line_id));
+            self.push_details(log_contents, *line_id);
+        }
+        if dropped > 0 {
+            self.rows.push_front(Row::Dropped(dropped));
+        }
+        self.rows_revision = Some(log_contents.get_revision());
+    }
+
+    fn push_details(&mut self, log_contents: &LinesState, line_id: usize) {
+        if self.expanded_lines.contains(&line_id) {
+            if let Some(json) = log_contents.get_pretty_json(line_id) {
+                self.rows.extend(json.lines().map(|line| Row::Details(line.to_string())));
+            }
+        }
+    }
+
+    /// Draw filtered lines into the ui. Keyboard shortcuts are handled only when the panel has focus.
+    pub fn draw(&mut self, ui: &mut Ui, session: &mut Session, profile: Option<&Profile>, log_contents: &mut LinesState,
+        search: &Search, has_focus: bool) {
+        if has_focus {
+            ui.input(|i| {
+                if i.key_pressed(egui::Key::C) && (i.modifiers.command || i.modifiers.ctrl) {
+                    log_contents.copy_selected_to_clipboard();
+                }
+                if i.key_pressed(egui::Key::B) && (i.modifiers.command || i.modifiers.ctrl) {
+                    if let Some(line_id) = log_contents.get_first_selected() {
+                        toggle_bookmark(session, log_contents, line_id);
+                    }
+                }
+            });
+        }
+        let bookmarked_lines: HashSet<usize> = session.get_bookmarks()
+            .iter()
+            .filter_map(|bookmark| log_contents.find_line(bookmark.get_key()))
+            .collect();
 
         let frame = egui::containers::Frame {
             inner_margin: egui::style::Margin { left: 0., right: 0., top: 0., bottom: 0. },
@@ -52,79 +237,201 @@ impl MainPanel {
             fill: session.get_colors().background(),
             stroke: egui::Stroke::new(0.0, Color32::BLACK),
         };
-        CentralPanel::default().frame(frame).show(ctx, |ui| {
-            let filtered_lines = log_contents.get_filtered_lines(filter_text).clone();
-            egui::ScrollArea::both()
+        let compact_records = *session.get_compact_records();
+        let session_colors = session.get_colors().clone();
+        let show_ansi_colors = *session.get_show_ansi_colors();
+        let is_json = log_contents.get_format_name() == Some("json");
+        frame.show(ui, |ui| {
+            self.update_rows(log_contents);
+            let font_id = FontId::monospace(self.font_size);
+            let row_height = ui.fonts(|fonts| fonts.row_height(&font_id));
+            ui.spacing_mut().item_spacing.y = self.line_spacing;
+
+            let mut scroll_area = egui::ScrollArea::both()
                 .auto_shrink([false, false])
-                .stick_to_bottom(true)
-                .show_rows(ui, 12.0, filtered_lines.len(),
+                .stick_to_bottom(self.is_following);
+            if let Some((line_id, margin_rows)) = self.scroll_to_line.take() {
+                if let Some(row) = self.rows.iter().position(|row| matches!(row, Row::Line(id) if *id == line_id)) {
+                    let row_height = row_height + self.line_spacing;
+                    scroll_area = scroll_area.vertical_scroll_offset(row.saturating_sub(margin_rows) as f32 * row_height);
+                }
+            } else if self.scroll_to_end {
+                self.scroll_to_end = false;
+                scroll_area = scroll_area.vertical_scroll_offset(f32::MAX);
+            }
+            scroll_area
+                .show_rows(ui, row_height, self.rows.len(),
        |ui, row_range| {
+                       self.top_line = self.rows.range(row_range.clone()).find_map(|row| match row {
+                           Row::Line(line_id) => Some(*line_id),
+                           _ => None,
+                       });
                        for row in row_range {
-                           let line = &filtered_lines[row].0;
-                           let is_selected = log_contents.is_selected(filtered_lines[row].1 as usize);
+                           let line_id = match &self.rows[row] {
+                               Row::Line(line_id) => *line_id,
+                               Row::Details(text) => {
+                                   let details_format = TextFormat {
+                                       color: session.get_colors().foreground(),
+                                       font_id: FontId::monospace(self.font_size),
+                                       ..Default::default()
+                                   };
+                                   let mut layout_job = LayoutJob::default();
+                                   layout_job.append(&format!("    {}", text), 0.0, details_format);
+                                   ui.add(Label::new(layout_job).wrap(false));
+                                   continue;
+                               },
+                               Row::Dropped(count) => {
+                                   ui.add(Label::new(RichText::new(format!("⋯ {} earlier lines dropped", count))
+                                       .font(font_id.clone())
+                                       .italics()
+                                       .color(session.get_colors().foreground())))
+                                       .on_hover_text("Lines beyond the limits set in session settings are not kept");
+                                   continue;
+                               },
+                           };
+                           let Some(line) = log_contents.get_line(line_id) else {
+                               continue;
+                           };
+                           let is_selected = log_contents.is_selected(line_id);
+                           let record_lines = log_contents.get_record_range(line_id);
+                           // Continuation lines are highlighted as the line starting their record
+                           let highlight_line = log_contents.get_line(record_lines.start).unwrap_or(line);
+                           let level = log_contents.get_level(line_id);
+                           let record = if compact_records || is_json {
+                               log_contents.get_record(line_id)
+                           } else {
+                               None
+                           };
+                           let is_compact = compact_records && record.is_some();
+                           let text = match &record {
+                               Some(record) if compact_records => compact_line(record, level),
+                               _ => line.clone(),
+                           };
+                           let mut line_colors = self.get_readable_colors(session.get_highlight(profile, highlight_line, level), &session_colors);
+                           // Standard error of commands stands out unless highlighted otherwise
+                           if log_contents.is_error_line(line_id) && line_colors == session_colors {
+                               line_colors.foreground = color32_to_rgb(ui.visuals().error_fg_color);
+                               line_colors = self.get_readable_colors(&line_colors, &session_colors);
+                           }
                            let text_format = TextFormat {
-                               background: session.get_highlight(line).background(),
-                               color: session.get_highlight(line).foreground(),
-                               font_id: FontId::monospace(12.0),
+                               background: line_colors.background(),
+                               color: line_colors.foreground(),
+                               font_id: FontId::monospace(self.font_size),
                                ..Default::default()
                            };
                            let inverted_text_format = TextFormat {
-                               background: session.get_highlight(line).foreground(),
-                               color: session.get_highlight(line).background(),
-                               font_id: FontId::monospace(12.0),
+                               background: line_colors.foreground(),
+                               color: line_colors.background(),
+                               font_id: FontId::monospace(self.font_size),
                                ..Default::default()
                            };
                            let selected_text_format = TextFormat {
-                               background: Color32::BLUE,
-                               color: Color32::WHITE,
-                               font_id: FontId::monospace(12.0),
+                               background: ui.visuals().selection.bg_fill,
+                               color: ui.visuals().selection.stroke.color,
+                               font_id: FontId::monospace(self.font_size),
                                ..Default::default()
                            };
 
-                           let found_ranges = if let Some(regex) = search_pattern {
-                               find_ranges(line, regex)
-                           } else {
-                               vec![]
+                           let current_match_format = TextFormat {
+                               background: Color32::from_rgb(255, 165, 0),
+                               color: Color32::BLACK,
+                               font_id: FontId::monospace(self.font_size),
+                               ..Default::default()
                            };
+                           let is_current_match = search.get_current() == Some(line_id);
 
-                           let found_ranges = fill_empty_ranges(found_ranges, line.len());
-                           let mut layout_sections = vec![];
-                           for (start, end, invert) in found_ranges {
-                               let format = if invert {
-                                   inverted_text_format.clone()
-                               } else if is_selected {
-                                   selected_text_format.clone()
-                               } else {
-                                   text_format.clone()
-                               };
-                               layout_sections.push(LayoutSection {
-                                   leading_space: 0.0,
-                                   byte_range: start..end,
-                                   format,
-                               });
+                           let mut layers = vec![];
+                           if !is_selected && show_ansi_colors && !is_compact {
+                               layers.extend(log_contents.get_line_styles(line_id).iter().map(|span| {
+                                   let colors = Colors {
+                                       foreground: span.style.foreground.map_or(line_colors.foreground, ansi_to_rgb),
+                                       background: span.style.background.map_or(line_colors.background, ansi_to_rgb),
+                                   };
+                                   let colors = self.get_readable_colors(&colors, &session_colors);
+                                   let span_format = TextFormat {
+                                       background: colors.background(),
+                                       color: colors.foreground(),
+                                       italics: span.style.is_italic,
+                                       underline: if span.style.is_underline { Stroke::new(1.0, colors.foreground()) } else { Stroke::NONE },
+                                       font_id: FontId::monospace(self.font_size),
+                                       ..Default::default()
+                                   };
+                                   (span.start, span.end, span_format)
+                               }));
                            }
+                           if !is_selected {
+                               layers.extend(session.get_span_highlights(profile, &text).into_iter().map(|(start, end, colors)| {
+                                   let colors = self.get_readable_colors(colors, &session_colors);
+                                   let span_format = TextFormat {
+                                       background: colors.background(),
+                                       color: colors.foreground(),
+                                       font_id: FontId::monospace(self.font_size),
+                                       ..Default::default()
+                                   };
+                                   (start, end, span_format)
+                               }));
+                           }
+                           if let Some(regex) = search.get_regex() {
+                               let found_format = if is_current_match { &current_match_format } else { &inverted_text_format };
+                               layers.extend(find_ranges(&text, regex).into_iter().map(|(start, end)| (start, end, found_format.clone())));
+                           }
+                           let base_format = if is_selected { &selected_text_format } else { &text_format };
+                           let layout_sections = layer_sections(text.len(), base_format, &layers);
 
                            let layout_job = LayoutJob {
                                sections: layout_sections,
-                               text: line.clone(),
+                               text,
                                break_on_newline: false,
                                ..Default::default()
                            };
                            let line_label = Label::new(layout_job)
                                .wrap(false)
                                .sense(Sense::click());
-                           if ui.add(line_label)
-                               .context_menu(|ui| self.nested_menus(ui, log_contents, row))
-                               .clicked() {
-                               let modifiers = ui.input(|i| i.modifiers);
-                               if modifiers.ctrl || modifiers.command {
-                                   log_contents.toggle_add_selection(filtered_lines[row].1 as usize);
-                               } else if modifiers.shift {
-                                   log_contents.toggle_add_range_selection(filtered_lines[row].1 as usize);
-                               } else {
-                                   log_contents.toggle_single_line_selection(filtered_lines[row].1 as usize);
+                           ui.horizontal(|ui| {
+                               ui.spacing_mut().item_spacing.x = 0.0;
+                               let gutter = Label::new(RichText::new(if bookmarked_lines.contains(&line_id) { "● " } else { "  " })
+                                   .font(font_id.clone())
+                                   .color(Color32::from_rgb(255, 165, 0)))
+                                   .sense(Sense::click());
+                               if ui.add(gutter).on_hover_text("Toggle bookmark (Ctrl/Cmd+B)").clicked() {
+                                   toggle_bookmark(session, log_contents, line_id);
                                }
-                           }
+                               if record_lines.start == line_id && record_lines.len() > 1 {
+                                   let is_collapsed = log_contents.is_collapsed(line_id);
+                                   let expander = Label::new(RichText::new(if is_collapsed { "▸ " } else { "▾ " })
+                                       .font(font_id.clone())
+                                       .color(session.get_colors().foreground()))
+                                       .sense(Sense::click());
+                                   if ui.add(expander).on_hover_text(format!("{} more lines", record_lines.len() - 1)).clicked() {
+                                       log_contents.toggle_collapsed(line_id);
+                                   }
+                               } else if is_json && record.is_some() {
+                                   let is_expanded = self.expanded_lines.contains(&line_id);
+                                   let expander = Label::new(RichText::new(if is_expanded { "▾ " } else { "▸ " })
+                                       .font(font_id.clone())
+                                       .color(session.get_colors().foreground()))
+                                       .sense(Sense::click());
+                                   if ui.add(expander).on_hover_text("Show full JSON").clicked() {
+                                       if !self.expanded_lines.remove(&line_id) {
+                                           self.expanded_lines.insert(line_id);
+                                       }
+                                       self.rows_revision = None;
+                                   }
+                               }
+
+                               if ui.add(line_label)
+                                   .context_menu(|ui| self.nested_menus(ui, session, log_contents, line_id))
+                                   .clicked() {
+                                   let modifiers = ui.input(|i| i.modifiers);
+                                   if modifiers.ctrl || modifiers.command {
+                                       log_contents.toggle_add_selection(line_id);
+                                   } else if modifiers.shift {
+                                       log_contents.toggle_add_range_selection(line_id);
+                                   } else {
+                                       log_contents.toggle_single_line_selection(line_id);
+                                   }
+                               }
+                           });
                        }
                     ui.add(Label::new(""));
                     ui.add(Label::new(""));
@@ -132,7 +439,7 @@ impl MainPanel {
         });
     }
 
-    fn nested_menus(&mut self, ui: &mut egui::Ui, log_contents: &mut LinesState, row: usize) {
+    fn nested_menus(&mut self, ui: &mut egui::Ui, session: &mut Session, log_contents: &mut LinesState, row: usize) {
         if !log_contents.is_selected(row) {
             log_contents.toggle_single_line_selection(row);
         }
@@ -140,5 +447,9 @@ impl MainPanel {
             log_contents.copy_selected_to_clipboard();
             ui.close_menu();
         }
+        if ui.button("Toggle bookmark (Ctrl/Cmd+B)").clicked() {
+            toggle_bookmark(session, log_contents, row);
+            ui.close_menu();
+        }
     }
 }
\ No newline at end of file
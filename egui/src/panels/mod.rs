@@ -1,2 +1,3 @@
+pub mod bookmarks;
 pub mod session_settings;
 pub mod main;
@@ -1,11 +1,14 @@
 use egui::color_picker::color_edit_button_rgb;
-use egui::{Context, TextEdit};
-use crate::highlight::{Highlight};
+use egui::{Color32, ComboBox, Context, DragValue, TextEdit};
+use tailor::LevelClassifier;
+use crate::profile::Profiles;
 use crate::session::Session;
+use crate::widgets::highlights::HighlightsEdit;
 
 #[derive(Default)]
 pub struct SessionSettingsPanel {
     is_visible: bool,
+    highlights_edit: HighlightsEdit,
 }
 
 impl SessionSettingsPanel {
@@ -17,7 +20,7 @@ impl SessionSettingsPanel {
         self.is_visible
     }
 
-    pub fn draw(&mut self, ctx: &Context, session: &mut Session) {
+    pub fn draw(&mut self, ctx: &Context, session: &mut Session, profiles: &Profiles) {
         if !self.is_visible {
             session.save();
             return;
@@ -31,35 +34,60 @@ impl SessionSettingsPanel {
                 ui.vertical_centered(|ui| {
                     ui.heading("Session");
                 });
+                if let Some(error) = session.get_load_error() {
+                    ui.colored_label(Color32::RED, format!("Saved session could not be loaded: {}", error));
+                }
                 ui.horizontal(|ui| {
-                    ui.label("Text:");
-                    color_edit_button_rgb(ui, &mut session.get_colors().foreground);
-                    ui.label("Background:");
-                    color_edit_button_rgb(ui, &mut session.get_colors().background);
+                    ui.checkbox(session.get_follow_theme(), "Theme colours")
+                        .on_hover_text("Use text and background colours of the theme");
+                    if !*session.get_follow_theme() {
+                        ui.label("Text:");
+                        color_edit_button_rgb(ui, &mut session.get_colors().foreground);
+                        ui.label("Background:");
+                        color_edit_button_rgb(ui, &mut session.get_colors().background);
+                    }
                 });
-                ui.separator();
-                let mut remove_at = None;
-                for (index, highlight) in session.get_highlights().iter_mut().enumerate() {
-                    ui.horizontal(|ui| {
-                        if ui.button("🗑").clicked() {
-                            remove_at = Some(index);
-                        }
-                        let pattern_edit = TextEdit::singleline(highlight.get_pattern())
-                            .hint_text("Regex Pattern")
-                            .desired_width(145.0);
-                        if ui.add(pattern_edit).changed() {
-                            let _ = highlight.update_regex();
-                        }
-                        color_edit_button_rgb(ui, &mut highlight.get_mut_colors().foreground);
-                        color_edit_button_rgb(ui, &mut highlight.get_mut_colors().background);
-                    });
+                ui.checkbox(session.get_compact_records(), "Compact view of structured lines")
+                    .on_hover_text("Show parsed lines as time, level and message. JSON lines can be expanded.");
+                ui.checkbox(session.get_show_ansi_colors(), "Terminal colours")
+                    .on_hover_text("Colour text as ANSI escape sequences in lines tell. When off, the sequences are only removed.");
+                ui.checkbox(session.get_group_records(), "Group multi-line records")
+                    .on_hover_text("Keep stack traces and other continuation lines together with the line starting record");
+                if *session.get_group_records() {
+                    let record_start_edit = TextEdit::singleline(session.get_record_start_pattern())
+                        .hint_text("Record start regex, empty to guess")
+                        .desired_width(f32::INFINITY);
+                    ui.add(record_start_edit)
+                        .on_hover_text("Lines matching the pattern start records. When empty, detected log format or indentation is used.");
                 }
-                if let Some(index) = remove_at {
-                    session.remove_highlight(index);
-                }
-                if ui.button(" + ").clicked() {
-                    session.get_highlights().push(Highlight::default());
+                let level_words_edit = TextEdit::singleline(session.get_level_words())
+                    .hint_text("Extra levels, e.g. CRIT=fatal, 35=warn")
+                    .desired_width(f32::INFINITY);
+                ui.add(level_words_edit)
+                    .on_hover_text("Words or numbers telling log level, in addition to the usual ones like ERROR or WARN");
+                if let Err(error) = LevelClassifier::default().with_levels(session.get_level_words()) {
+                    ui.colored_label(Color32::RED, error);
                 }
+                ui.horizontal(|ui| {
+                    ui.label("Keep last");
+                    ui.add(DragValue::new(session.get_max_lines()).speed(1000).suffix(" lines"));
+                    ui.label("or");
+                    ui.add(DragValue::new(session.get_max_megabytes()).suffix(" MB"));
+                }).response.on_hover_text("The oldest lines are dropped when either limit is exceeded, 0 means no limit");
+                ui.separator();
+                let profile_name = session.find_profile(profiles).map_or("None", |profile| profile.get_name()).to_string();
+                ComboBox::from_label("Profile")
+                    .selected_text(profile_name)
+                    .width(160.0)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(session.get_profile(), None, "None");
+                        for profile in profiles.iter() {
+                            ui.selectable_value(session.get_profile(), Some(profile.get_id()), profile.get_name());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Shared highlights used after the session ones below");
+                self.highlights_edit.draw(ui, "session", session.get_highlights());
 
                 if ui.button("Save").clicked() {
                     session.save();
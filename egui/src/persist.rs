@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use serde_json::Value;
+
+/// Key of the version saved files are written by
+const VERSION_KEY: &str = "version";
+
+/// Hash of the text staying the same between Rust releases, unlike `DefaultHasher` (64-bit FNV-1a)
+pub fn stable_hash(text: &str) -> u64 {
+    text.bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Bring saved JSON object to the current version, which is the number of migrations.
+/// Objects without version run all migrations, each one migrates from the version of its index.
+pub fn migrate(saved: &mut Value, migrations: &[fn(&mut Value)]) -> Result<(), String> {
+    if !saved.is_object() {
+        return Err("is not an object".to_string());
+    }
+
+    let current = migrations.len() as u64;
+    let version = saved.get(VERSION_KEY).and_then(Value::as_u64).unwrap_or(0);
+    if version > current {
+        return Err(format!("is saved by newer version {} of the app", version));
+    }
+    for migration in &migrations[version as usize..] {
+        migration(saved);
+    }
+    saved[VERSION_KEY] = current.into();
+    Ok(())
+}
+
+/// Move the file aside, next to it, returning its new path
+pub fn back_up(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_string_lossy();
+    let backup_path = path.with_file_name(format!("{}.{}.bak", file_name, Utc::now().format("%Y%m%d%H%M%S")));
+    std::fs::rename(path, &backup_path).ok()?;
+    Some(backup_path)
+}
+
+/// Read and parse saved file, none when there is no such file. Files which fail to parse
+/// are backed up, so that saving again does not overwrite them.
+pub fn load_or_back_up<T>(path: &Path, parse: impl FnOnce(&str) -> Result<T, Box<dyn Error>>) -> Result<Option<T>, Box<dyn Error>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+
+    match parse(&text) {
+        Ok(parsed) => Ok(Some(parsed)),
+        Err(error) => match back_up(path) {
+            Some(backup_path) => Err(format!("{}, the file is backed up to {}", error, backup_path.display()).into()),
+            None => Err(error),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use serde_json::{json, Value};
+    use crate::persist::{load_or_back_up, migrate, stable_hash};
+
+    fn rename_path_to_source(saved: &mut Value) {
+        if let Some(path) = saved.as_object_mut().and_then(|saved| saved.remove("path")) {
+            saved["source"] = json!({ "File": path });
+        }
+    }
+
+    fn add_profile(saved: &mut Value) {
+        saved["profile"] = Value::Null;
+    }
+
+    #[test]
+    fn stable_hash_pinned() {
+        // Saved files are named by the hash, so it must never change
+        assert_eq!(stable_hash(""), 0xcbf29ce484222325);
+        assert_eq!(stable_hash("/var/log/syslog"), 0xbbef047a75c282ea);
+    }
+
+    #[test]
+    fn migrate_unversioned() {
+        let mut saved = json!({ "path": "/var/log/syslog", "colors": [1, 2, 3] });
+        assert!(migrate(&mut saved, &[rename_path_to_source, add_profile]).is_ok());
+        assert_eq!(saved, json!({ "colors": [1, 2, 3], "source": { "File": "/var/log/syslog" }, "profile": null, "version": 2 }));
+    }
+
+    #[test]
+    fn migrate_versioned() {
+        let mut saved = json!({ "version": 1, "source": "Stdin" });
+        assert!(migrate(&mut saved, &[rename_path_to_source, add_profile]).is_ok());
+        assert_eq!(saved, json!({ "version": 2, "source": "Stdin", "profile": null }));
+
+        let mut saved = json!({ "version": 3 });
+        assert!(migrate(&mut saved, &[rename_path_to_source, add_profile]).is_err());
+        assert!(migrate(&mut json!([]), &[]).is_err());
+    }
+
+    #[test]
+    fn corrupt_file_backed_up() {
+        let dir = std::env::temp_dir().join("tailor_persist_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(std::fs::create_dir_all(&dir).is_ok());
+        let path = dir.join("corrupt.json");
+        assert!(std::fs::write(&path, "{ not json").is_ok());
+
+        let parse = |text: &str| Ok(serde_json::from_str::<Value>(text)?);
+        let error = load_or_back_up(&path, parse).unwrap_err().to_string();
+        assert!(error.contains("backed up to"));
+        assert!(!path.exists());
+        let backups: Vec<PathBuf> = std::fs::read_dir(&dir).unwrap().flatten().map(|entry| entry.path()).collect();
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].to_string_lossy().contains("corrupt.json."));
+        assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), "{ not json");
+
+        assert!(matches!(load_or_back_up(&path, parse), Ok(None)));
+        assert!(std::fs::write(&path, "{}").is_ok());
+        assert!(matches!(load_or_back_up(&path, parse), Ok(Some(value)) if value == json!({})));
+        assert!(std::fs::remove_dir_all(&dir).is_ok());
+    }
+}
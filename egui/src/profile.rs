@@ -0,0 +1,209 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use app_dirs2::{AppDataType, get_app_root};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::APP_INFO;
+use crate::highlight::Highlight;
+use crate::persist::load_or_back_up;
+
+const PROFILES_FILENAME: &str = "profiles.json";
+
+/// Profile as exported to file, without id which is given by profiles it is imported to
+#[derive(Serialize)]
+struct ExportedProfile<'a> {
+    name: &'a str,
+    file_patterns: &'a str,
+    highlights: &'a [Highlight],
+}
+
+/// Named highlights shared by sessions, e.g. for a log format
+#[derive(Serialize, Deserialize, Default)]
+pub struct Profile {
+    /// Identity sessions refer to the profile by, kept when it is renamed. 0 until added to profiles.
+    #[serde(default)]
+    id: u64,
+    name: String,
+    /// Globs of file names the profile is picked for, separated by commas, e.g. `*.log, nginx*`
+    #[serde(default)]
+    file_patterns: String,
+    highlights: Vec<Highlight>,
+}
+
+impl Profile {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+
+    /// Read profile exported to the file
+    pub fn import(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let profile_json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&profile_json)?)
+    }
+
+    /// JSON of the profile to share, see `Profile::import`
+    pub fn export(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&ExportedProfile {
+            name: &self.name,
+            file_patterns: &self.file_patterns,
+            highlights: &self.highlights,
+        })
+    }
+
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_mut_name(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn get_file_patterns(&mut self) -> &mut String {
+        &mut self.file_patterns
+    }
+
+    pub fn get_highlights(&self) -> &[Highlight] {
+        &self.highlights
+    }
+
+    pub fn get_mut_highlights(&mut self) -> &mut Vec<Highlight> {
+        &mut self.highlights
+    }
+
+    /// Whether name of the file matches any of the profile globs
+    pub fn is_matching_file(&self, path: &Path) -> bool {
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+
+        self.file_patterns.split(',')
+            .map(str::trim)
+            .filter(|glob| !glob.is_empty())
+            .filter_map(glob_to_regex)
+            .any(|regex| regex.is_match(file_name))
+    }
+}
+
+/// Regex matching whole text against glob with `*` and `?` wildcards
+fn glob_to_regex(glob: &str) -> Option<Regex> {
+    let pattern: String = glob.chars()
+        .map(|c| match c {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            _ => regex::escape(&c.to_string()),
+        })
+        .collect();
+    Regex::new(&format!("^{}$", pattern)).ok()
+}
+
+/// Profiles shared by all sessions, kept in the app data folder
+#[derive(Serialize, Deserialize, Default)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+    /// Why saved profiles could not be loaded
+    #[serde(skip)]
+    load_error: Option<String>,
+}
+
+impl Profiles {
+    /// Load saved profiles. Profiles which could not be loaded are backed up and the error
+    /// is kept to be shown, so that saving does not lose them silently.
+    pub fn try_load() -> Self {
+        Self::load().unwrap_or_else(|error| {
+            log::warn!("Failed to load profiles: {}", error);
+            Self {
+                load_error: Some(error.to_string()),
+                ..Default::default()
+            }
+        })
+    }
+
+    pub fn save(&self) {
+        if let Ok(path) = Self::get_save_path() {
+            let profiles_json = serde_json::to_string(&self).unwrap_or("{}".to_string());
+            let _ = std::fs::write(path, profiles_json);
+        }
+    }
+
+    pub fn get_profiles(&mut self) -> &mut Vec<Profile> {
+        &mut self.profiles
+    }
+
+    pub fn get_load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    /// Add the profile with new id, returning its index
+    pub fn add(&mut self, mut profile: Profile) -> usize {
+        profile.id = self.next_id();
+        self.profiles.push(profile);
+        self.profiles.len() - 1
+    }
+
+    /// Put the profile in place of the one at the index, keeping its id
+    pub fn replace(&mut self, idx: usize, mut profile: Profile) {
+        profile.id = self.profiles[idx].id;
+        self.profiles[idx] = profile;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Profile> {
+        self.profiles.iter()
+    }
+
+    pub fn get_names(&self) -> Vec<String> {
+        self.profiles.iter().map(|profile| profile.name.clone()).collect()
+    }
+
+    pub fn find(&self, id: Option<u64>) -> Option<&Profile> {
+        let id = id?;
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+
+    /// The first profile picked for files with such name
+    pub fn find_for_file(&self, path: &Path) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.is_matching_file(path))
+    }
+
+    fn next_id(&self) -> u64 {
+        self.profiles.iter().map(|profile| profile.id).max().unwrap_or(0) + 1
+    }
+
+    fn load() -> Result<Self, Box<dyn Error>> {
+        let path = Self::get_save_path()?;
+        let profiles = load_or_back_up(&path, |profiles_json| Ok(serde_json::from_str(profiles_json)?))?;
+        Ok(profiles.unwrap_or_default())
+    }
+
+    fn get_save_path() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(get_app_root(AppDataType::UserData, &APP_INFO)?.join(PROFILES_FILENAME))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+    use crate::profile::{Profile, Profiles};
+
+    #[test]
+    fn exported_without_id() {
+        let mut profiles = Profiles::default();
+        profiles.add(Profile::new("First".to_string()));
+        let idx = profiles.add(Profile::new("Nginx".to_string()));
+        let profile = &mut profiles.get_profiles()[idx];
+        *profile.get_file_patterns() = "nginx*".to_string();
+        assert_ne!(profile.get_id(), 0);
+
+        let exported: Value = serde_json::from_str(&profile.export().unwrap()).unwrap();
+        assert!(exported.get("id").is_none());
+        let imported: Profile = serde_json::from_value(exported).unwrap();
+        assert_eq!(imported.get_id(), 0);
+        assert_eq!(imported.get_name(), "Nginx");
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use egui::Context;
+use regex::Regex;
+use crate::lines::LinesState;
+
+/// Lines searched at once, the lines stay locked meanwhile
+const SEARCH_CHUNK: usize = 10_000;
+
+/// Lines matching search regex and the current match navigated to
+///
+/// Lines are searched on a background thread, in chunks so the UI thread is not blocked
+/// for long, and the thread keeps searching new lines until the search changes.
+pub struct Search {
+    regex: Option<Regex>,
+    /// Ids of matching lines, ascending
+    found_lines: Arc<Mutex<Vec<usize>>>,
+    /// Tells the background thread to stop
+    is_cancelled: Arc<AtomicBool>,
+    /// Found lines currently shown, i.e. passing the filter
+    visible_matches: Vec<usize>,
+    /// Number of found lines and revision of filtered lines visible matches were taken from
+    visible_revision: Option<(usize, u64)>,
+    current: Option<usize>,
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self {
+            regex: None,
+            found_lines: Arc::new(Mutex::new(vec![])),
+            is_cancelled: Arc::new(AtomicBool::new(false)),
+            visible_matches: vec![],
+            visible_revision: None,
+            current: None,
+        }
+    }
+
+    /// Start searching for the regex, none to stop searching
+    pub fn set_regex(&mut self, regex: Option<Regex>, log_contents: Arc<Mutex<LinesState>>, ctx: Context) {
+        self.is_cancelled.store(true, Ordering::Relaxed);
+        self.found_lines = Arc::new(Mutex::new(vec![]));
+        self.is_cancelled = Arc::new(AtomicBool::new(false));
+        self.visible_matches.clear();
+        self.visible_revision = None;
+        self.current = None;
+        self.regex = regex.clone();
+
+        if let Some(regex) = regex {
+            let found_lines = self.found_lines.clone();
+            let is_cancelled = self.is_cancelled.clone();
+            std::thread::spawn(move || {
+                let mut searched_until = 0;
+                while !is_cancelled.load(Ordering::Relaxed) {
+                    let (found, dropped, is_done) = match log_contents.lock() {
+                        Ok(lines) => {
+                            if searched_until > lines.get_end() {
+                                // Lines were cleared, e.g. followed file was replaced
+                                searched_until = 0;
+                                if let Ok(mut found_lines) = found_lines.lock() {
+                                    found_lines.clear();
+                                }
+                            }
+                            let from = searched_until.max(lines.get_dropped_count());
+                            let to = lines.get_end().min(from + SEARCH_CHUNK);
+                            searched_until = to;
+                            let found: Vec<usize> = (from..to)
+                                .filter(|idx| lines.get_line(*idx).is_some_and(|line| regex.is_match(line)))
+                                .collect();
+                            (found, lines.get_dropped_count(), to == lines.get_end())
+                        },
+                        Err(_) => break,
+                    };
+
+                    if let Ok(mut found_lines) = found_lines.lock() {
+                        let kept = found_lines.partition_point(|idx| *idx < dropped);
+                        found_lines.drain(..kept);
+                        if !found.is_empty() {
+                            found_lines.extend(found);
+                            ctx.request_repaint();
+                        }
+                    }
+                    if is_done {
+                        std::thread::sleep(Duration::from_millis(200));
+                    }
+                }
+            });
+        }
+    }
+
+    pub fn get_regex(&self) -> &Option<Regex> {
+        &self.regex
+    }
+
+    /// Id of line with the current match
+    pub fn get_current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// Position of the current match among the visible ones, counting from 1, and their count
+    pub fn get_position(&mut self, lines: &LinesState) -> (Option<usize>, usize) {
+        self.update_visible_matches(lines);
+        let position = self.current
+            .and_then(|current| self.visible_matches.binary_search(&current).ok())
+            .map(|idx| idx + 1);
+        (position, self.visible_matches.len())
+    }
+
+    /// Move to the first visible match below the current one, or the first one.
+    /// Returns id of line with the match.
+    pub fn next(&mut self, lines: &LinesState) -> Option<usize> {
+        self.update_visible_matches(lines);
+        let next = match self.current {
+            Some(current) => self.visible_matches.partition_point(|idx| *idx <= current),
+            None => 0,
+        };
+        self.current = self.visible_matches.get(next).or(self.visible_matches.first()).copied();
+        self.current
+    }
+
+    /// Move to the last visible match above the current one, or the last one.
+    /// Returns id of line with the match.
+    pub fn previous(&mut self, lines: &LinesState) -> Option<usize> {
+        self.update_visible_matches(lines);
+        let previous = match self.current {
+            Some(current) => self.visible_matches.partition_point(|idx| *idx < current).checked_sub(1),
+            None => None,
+        };
+        self.current = previous.and_then(|idx| self.visible_matches.get(idx)).or(self.visible_matches.last()).copied();
+        self.current
+    }
+
+    fn update_visible_matches(&mut self, lines: &LinesState) {
+        let Ok(found_lines) = self.found_lines.lock() else {
+            return;
+        };
+        let revision = (found_lines.len(), lines.get_revision());
+        if self.visible_revision == Some(revision) {
+            return;
+        }
+
+        let filtered_lines = lines.get_filtered_lines();
+        self.visible_matches = found_lines.iter()
+            .filter(|idx| filtered_lines.binary_search(idx).is_ok())
+            .copied()
+            .collect();
+        self.visible_revision = Some(revision);
+    }
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        self.is_cancelled.store(true, Ordering::Relaxed);
+    }
+}
@@ -1,84 +1,237 @@
 use std::collections::hash_map::DefaultHasher;
 use std::error::Error;
 use std::hash::{Hash, Hasher};
-use std::path::{Component, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use app_dirs2::{AppDataType, get_app_root};
+use egui::Visuals;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use crate::APP_INFO;
-use crate::highlight::{Colors, Highlight};
-
-fn default_highlights() -> Vec<Highlight> {
-    vec![
-        Highlight::new(String::from("ERROR"), Colors { foreground: [1.0, 1.0, 1.0], background: [0.75, 0.0, 0.0] }).unwrap(),
-        Highlight::new(String::from("WARN"), Colors { foreground: [0.84, 0.66, 0.39], background: [0.0, 0.0, 0.0] }).unwrap(),
-        Highlight::new(String::from("DEBUG"), Colors { foreground: [0.35, 0.76, 0.35], background: [0.0, 0.0, 0.0] }).unwrap(),
-        Highlight::new(String::from("INFO"), Colors { foreground: [0.53, 0.53, 0.86], background: [0.0, 0.0, 0.0] }).unwrap(),
-    ]
+use tailor::LogLevel;
+use crate::bookmark::{Bookmark, LineKey};
+use crate::highlight::{Colors, Highlight, HighlightScope};
+use crate::persist::{load_or_back_up, migrate, stable_hash};
+use crate::profile::{Profile, Profiles};
+use crate::settings::Settings;
+use crate::source::Source;
+
+/// Level highlights for dark or light theme
+fn default_highlights(is_dark: bool) -> Vec<Highlight> {
+    if is_dark {
+        vec![
+            Highlight::for_level(LogLevel::Fatal, Colors { foreground: [1.0, 1.0, 0.0], background: [0.75, 0.0, 0.0] }),
+            Highlight::for_level(LogLevel::Error, Colors { foreground: [1.0, 1.0, 1.0], background: [0.75, 0.0, 0.0] }),
+            Highlight::for_level(LogLevel::Warning, Colors { foreground: [0.84, 0.66, 0.39], background: [0.0, 0.0, 0.0] }),
+            Highlight::for_level(LogLevel::Debug, Colors { foreground: [0.35, 0.76, 0.35], background: [0.0, 0.0, 0.0] }),
+            Highlight::for_level(LogLevel::Trace, Colors { foreground: [0.35, 0.56, 0.35], background: [0.0, 0.0, 0.0] }),
+            Highlight::for_level(LogLevel::Info, Colors { foreground: [0.53, 0.53, 0.86], background: [0.0, 0.0, 0.0] }),
+        ]
+    } else {
+        vec![
+            Highlight::for_level(LogLevel::Fatal, Colors { foreground: [1.0, 1.0, 0.0], background: [0.6, 0.0, 0.0] }),
+            Highlight::for_level(LogLevel::Error, Colors { foreground: [1.0, 1.0, 1.0], background: [0.75, 0.0, 0.0] }),
+            Highlight::for_level(LogLevel::Warning, Colors { foreground: [0.6, 0.38, 0.0], background: [1.0, 1.0, 1.0] }),
+            Highlight::for_level(LogLevel::Debug, Colors { foreground: [0.1, 0.45, 0.1], background: [1.0, 1.0, 1.0] }),
+            Highlight::for_level(LogLevel::Trace, Colors { foreground: [0.3, 0.42, 0.3], background: [1.0, 1.0, 1.0] }),
+            Highlight::for_level(LogLevel::Info, Colors { foreground: [0.2, 0.2, 0.65], background: [1.0, 1.0, 1.0] }),
+        ]
+    }
+}
+
+/// Version of saved sessions, increased when older ones need migration
+const SESSION_VERSION: u32 = 1;
+
+fn default_show_ansi_colors() -> bool {
+    true
+}
+
+fn default_max_lines() -> usize {
+    1_000_000
 }
 
 #[derive(Serialize,Deserialize)]
 pub struct Session {
-    path: PathBuf,
+    /// Version the session was saved by, see `MIGRATIONS`
+    #[serde(default)]
+    version: u32,
+    source: Source,
     colors: Colors,
+    /// Colours are taken from the theme, see `Session::apply_theme`
+    #[serde(default)]
+    follow_theme: bool,
+    /// Highlights used before the profile ones, overriding them
     highlights: Vec<Highlight>,
+    /// Id of shared profile with more highlights
+    #[serde(default)]
+    profile: Option<u64>,
+    /// Show parsed records as `time level message` instead of raw lines
+    #[serde(default)]
+    compact_records: bool,
+    /// Keep continuation lines, e.g. stack traces, together with the line starting record
+    #[serde(default)]
+    group_records: bool,
+    /// Colour text as ANSI escape sequences in lines tell, the sequences are removed anyway
+    #[serde(default = "default_show_ansi_colors")]
+    show_ansi_colors: bool,
+    /// Regex matching first lines of records, empty to guess
+    #[serde(default)]
+    record_start_pattern: String,
+    /// Extra level words, e.g. `CRIT=fatal, 35=warn`
+    #[serde(default)]
+    level_words: String,
+    /// Most lines kept in memory, 0 for no limit
+    #[serde(default = "default_max_lines")]
+    max_lines: usize,
+    /// Most megabytes of lines kept in memory, 0 for no limit
+    #[serde(default)]
+    max_megabytes: usize,
+    #[serde(default)]
+    bookmarks: Vec<Bookmark>,
+    #[serde(skip)]
+    load_error: Option<String>,
 }
 
 impl Default for Session {
     fn default() -> Self {
-        let default_path = PathBuf::from("");
-        if let Some(loaded) = Self::try_load(&default_path)
-        {
-            return loaded;
-        }
-
-        Self {
-            path: PathBuf::new(),
+        let source = Source::File(PathBuf::new());
+        Self::load_or(&source.clone(), || Self {
+            version: SESSION_VERSION,
+            source,
             colors: Colors::default(),
-            highlights: default_highlights(),
-        }
+            follow_theme: true,
+            highlights: default_highlights(true),
+            profile: None,
+            compact_records: false,
+            group_records: false,
+            show_ansi_colors: true,
+            record_start_pattern: String::new(),
+            level_words: String::new(),
+            max_lines: default_max_lines(),
+            max_megabytes: 0,
+            bookmarks: vec![],
+            load_error: None,
+        })
     }
 }
 
 impl Session {
-    pub fn new(path: PathBuf) -> Self {
-        if let Some(loaded) = Self::try_load(&path)
-        {
-            return loaded;
-        }
-
-        Self {
-            path,
-            colors: Colors::default(),
-            highlights: default_highlights(),
-        }
+    /// Load session of the source, or start new one with profile picked for the file name
+    /// and colours of the theme unless set in settings
+    pub fn new(source: Source, profiles: &Profiles, settings: &Settings, visuals: &Visuals) -> Self {
+        let profile = source.get_path()
+            .and_then(|path| profiles.find_for_file(path))
+            .map(|profile| profile.get_id());
+        Self::load_or(&source.clone(), || Self {
+            version: SESSION_VERSION,
+            source,
+            colors: settings.default_colors.clone().unwrap_or_else(|| Colors::from_visuals(visuals)),
+            follow_theme: settings.default_colors.is_none(),
+            highlights: if profile.is_some() { vec![] } else { default_highlights(visuals.dark_mode) },
+            profile,
+            compact_records: false,
+            group_records: false,
+            show_ansi_colors: true,
+            record_start_pattern: String::new(),
+            level_words: String::new(),
+            max_lines: settings.default_max_lines,
+            max_megabytes: 0,
+            bookmarks: vec![],
+            load_error: None,
+        })
     }
 
     pub fn save(&self) {
-        if let Ok(session_save_path) = Self::get_save_path(&self.path) {
+        if let Ok(session_save_path) = Self::get_save_path(&self.source) {
             let session_json = serde_json::to_string(&self).unwrap_or("{}".to_string());
             let _ = std::fs::write(session_save_path, session_json);
         }
     }
 
-    pub fn get_path(&self) -> &PathBuf {
-        &self.path
+    pub fn get_source(&self) -> &Source {
+        &self.source
     }
 
     pub fn get_colors(&mut self) -> &mut Colors {
         &mut self.colors
     }
 
+    pub fn get_follow_theme(&mut self) -> &mut bool {
+        &mut self.follow_theme
+    }
+
+    /// Take colours of the theme, unless the session has colours of its own
+    pub fn apply_theme(&mut self, visuals: &Visuals) {
+        if self.follow_theme {
+            self.colors = Colors::from_visuals(visuals);
+        }
+    }
+
+    pub fn get_compact_records(&mut self) -> &mut bool {
+        &mut self.compact_records
+    }
+
+    pub fn get_group_records(&mut self) -> &mut bool {
+        &mut self.group_records
+    }
+
+    pub fn get_show_ansi_colors(&mut self) -> &mut bool {
+        &mut self.show_ansi_colors
+    }
+
+    pub fn get_record_start_pattern(&mut self) -> &mut String {
+        &mut self.record_start_pattern
+    }
+
     pub fn get_highlights(&mut self) -> &mut Vec<Highlight> {
         &mut self.highlights
     }
 
-    pub fn remove_highlight(&mut self, index: usize) {
-        let _ = self.highlights.remove(index);
+    pub fn get_profile(&mut self) -> &mut Option<u64> {
+        &mut self.profile
     }
 
-    pub fn get_highlight(&self, line: &str) -> &Colors {
-        for highlight in &self.highlights {
-            if highlight.is_matching(line) {
+    /// Profile of the session among the shared ones, if it still exists
+    pub fn find_profile<'a>(&self, profiles: &'a Profiles) -> Option<&'a Profile> {
+        profiles.find(self.profile)
+    }
+
+    pub fn get_level_words(&mut self) -> &mut String {
+        &mut self.level_words
+    }
+
+    pub fn get_max_lines(&mut self) -> &mut usize {
+        &mut self.max_lines
+    }
+
+    pub fn get_max_megabytes(&mut self) -> &mut usize {
+        &mut self.max_megabytes
+    }
+
+    pub fn get_bookmarks(&mut self) -> &mut Vec<Bookmark> {
+        &mut self.bookmarks
+    }
+
+    /// Bookmark the line, or remove its bookmark, and save the session. Sessions of tabs
+    /// shown in split pane are not saved otherwise until they get active.
+    pub fn toggle_bookmark(&mut self, key: LineKey, text: &str) {
+        match self.bookmarks.iter().position(|bookmark| *bookmark.get_key() == key) {
+            Some(idx) => {
+                self.bookmarks.remove(idx);
+            },
+            None => self.bookmarks.push(Bookmark::new(key, text.to_string())),
+        }
+        self.save();
+    }
+
+    /// Session highlights followed by the profile ones
+    fn iter_highlights<'a>(&'a self, profile: Option<&'a Profile>) -> impl DoubleEndedIterator<Item = &'a Highlight> {
+        self.highlights.iter().chain(profile.into_iter().flat_map(|profile| profile.get_highlights()))
+    }
+
+    /// Colours of the first enabled line highlight matching the line, or the session colours
+    pub fn get_highlight<'a>(&'a self, profile: Option<&'a Profile>, line: &str, level: Option<LogLevel>) -> &'a Colors {
+        for highlight in self.iter_highlights(profile) {
+            if highlight.get_scope() == HighlightScope::Line && highlight.is_matching(line, level) {
                 return highlight.get_colors();
             }
         }
@@ -86,39 +239,138 @@ impl Session {
         &self.colors
     }
 
-    fn get_save_path(path: &PathBuf) -> Result<PathBuf, Box<dyn Error>> {
-        let data_path = get_app_root(AppDataType::UserData, &APP_INFO)?;
-        if path.display().to_string().is_empty() {
-            return Ok(data_path.join("default.json"))
-        }
+    /// Parts of the text coloured over the line, from the lowest priority to the highest,
+    /// so that later ones are drawn over the earlier ones
+    pub fn get_span_highlights<'a>(&'a self, profile: Option<&'a Profile>, text: &str) -> Vec<(usize, usize, &'a Colors)> {
+        self.iter_highlights(profile)
+            .rev()
+            .flat_map(|highlight| highlight.find_spans(text))
+            .collect()
+    }
 
-        let mut hasher = DefaultHasher::new();
-        path.hash(&mut hasher);
-        let h = hasher.finish();
-        let last_path_component = match path.components().next_back() {
-            Some(Component::Normal(dir_name)) => {
-                if let Some(dir_name) = dir_name.to_str() {
-                    dir_name.to_string()
-                } else {
-                    "".to_string()
+    /// Why saved session could not be loaded, the file is backed up then
+    pub fn get_load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    /// Load saved session of the source, or a new one telling why the saved one could not be loaded
+    fn load_or(source: &Source, new_session: impl FnOnce() -> Session) -> Session {
+        match Self::try_load(source) {
+            Ok(Some(session)) => session,
+            Ok(None) => new_session(),
+            Err(error) => {
+                log::warn!("Failed to load session of {}: {}", source, error);
+                Session {
+                    load_error: Some(error.to_string()),
+                    ..new_session()
                 }
             },
-            _ => "".to_string(),
+        }
+    }
+
+    fn get_save_path(source: &Source) -> Result<PathBuf, Box<dyn Error>> {
+        let session_save_name = match source {
+            Source::File(path) if path.as_os_str().is_empty() => "default.json".to_string(),
+            Source::File(path) => format!("{}_{}.json", stable_hash(&path.to_string_lossy()), get_last_component(path)),
+            Source::Command(command) => format!("command_{}.json", stable_hash(command)),
+            Source::Stdin => "stdin.json".to_string(),
         };
-        let session_save_name = format!("{}_{}.json", h, last_path_component);
-        Ok(data_path.join(session_save_name))
+        Ok(get_app_root(AppDataType::UserData, &APP_INFO)?.join(session_save_name))
     }
 
-    fn try_load(path: &PathBuf) -> Option<Session> {
-        if let Ok(session_save_path) = Self::get_save_path(path) {
-            if let Ok(loaded_session_json) = std::fs::read_to_string(session_save_path)
-            {
-                if let Ok(loaded_session) = serde_json::from_str(&loaded_session_json) {
-                    return Some(loaded_session);
-                }
-            }
+    /// Path sessions of files were saved to before versioning, named with hash that changes between Rust releases
+    fn get_legacy_save_path(source: &Source) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let path = match source {
+            Source::File(path) if !path.as_os_str().is_empty() => path,
+            _ => return Ok(None),
+        };
+
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        let session_save_name = format!("{}_{}.json", hasher.finish(), get_last_component(path));
+        Ok(Some(get_app_root(AppDataType::UserData, &APP_INFO)?.join(session_save_name)))
+    }
+
+    /// Saved session of the source, moving it from the legacy path if needed.
+    /// Unreadable files are backed up so that saving does not overwrite them.
+    fn try_load(source: &Source) -> Result<Option<Session>, Box<dyn Error>> {
+        let save_path = Self::get_save_path(source)?;
+        let legacy_path = Self::get_legacy_save_path(source)?
+            .filter(|legacy_path| !save_path.exists() && legacy_path.exists());
+        let session = load_or_back_up(legacy_path.as_ref().unwrap_or(&save_path), parse_session)?;
+        if let (Some(session), Some(legacy_path)) = (&session, &legacy_path) {
+            session.save();
+            let _ = std::fs::remove_file(legacy_path);
         }
+        Ok(session)
+    }
+}
+
+fn get_last_component(path: &Path) -> String {
+    match path.components().next_back() {
+        Some(Component::Normal(dir_name)) => dir_name.to_str().unwrap_or_default().to_string(),
+        _ => "".to_string(),
+    }
+}
+
+/// Sessions saved before versioning, which followed only files and kept them as `path`.
+/// Those with the default colours follow the theme.
+fn migrate_unversioned(session: &mut Value) {
+    if let Some(path) = session.as_object_mut().and_then(|session| session.remove("path")) {
+        session["source"] = json!({ "File": path });
+    }
+    let is_default = session.get("colors")
+        .and_then(|colors| serde_json::from_value::<Colors>(colors.clone()).ok())
+        .is_some_and(|colors| colors == Colors::default());
+    session["follow_theme"] = is_default.into();
+}
+
+/// Migrations of saved sessions to the next version, by version they migrate from
+const MIGRATIONS: [fn(&mut Value); SESSION_VERSION as usize] = [migrate_unversioned];
+
+/// Read saved session, migrating it from older version
+fn parse_session(session_json: &str) -> Result<Session, Box<dyn Error>> {
+    let mut session: Value = serde_json::from_str(session_json)?;
+    migrate(&mut session, &MIGRATIONS).map_err(|error| format!("session {}", error))?;
+    Ok(serde_json::from_value(session)?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use crate::session::{parse_session, SESSION_VERSION};
+    use crate::source::Source;
+
+    /// Session as saved before versioning, highlighting levels by default
+    const UNVERSIONED_SESSION: &str = r#"{"path":"/var/log/syslog","colors":{"foreground":[1.0,1.0,1.0],"background":[0.0,0.0,0.0]},"highlights":[{"pattern":"ERROR","regex":"(?i)ERROR","colors":{"foreground":[1.0,1.0,1.0],"background":[0.75,0.0,0.0]}},{"pattern":"WARN","regex":"(?i)WARN","colors":{"foreground":[0.84,0.66,0.39],"background":[0.0,0.0,0.0]}},{"pattern":"DEBUG","regex":"(?i)DEBUG","colors":{"foreground":[0.35,0.76,0.35],"background":[0.0,0.0,0.0]}},{"pattern":"INFO","regex":"(?i)INFO","colors":{"foreground":[0.53,0.53,0.86],"background":[0.0,0.0,0.0]}}]}"#;
+
+    #[test]
+    fn unversioned_session_migrated() {
+        let mut session = parse_session(UNVERSIONED_SESSION).unwrap();
+        assert_eq!(session.version, SESSION_VERSION);
+        assert_eq!(*session.get_source(), Source::File(PathBuf::from("/var/log/syslog")));
+        assert_eq!(session.get_colors().foreground, [1.0, 1.0, 1.0]);
+        assert_eq!(session.get_colors().background, [0.0, 0.0, 0.0]);
+        assert!(*session.get_follow_theme());
+        assert_eq!(session.get_highlights().len(), 4);
+        assert!(session.get_highlights()[0].is_matching("an error line", None));
+        assert!(!session.get_highlights()[0].is_matching("a warning line", None));
+        assert_eq!(session.get_profile(), &None);
+        assert!(session.get_bookmarks().is_empty());
+    }
+
+    #[test]
+    fn unversioned_session_colors_kept() {
+        let custom_colors = UNVERSIONED_SESSION.replacen("[0.0,0.0,0.0]", "[0.1,0.1,0.2]", 1);
+        let mut session = parse_session(&custom_colors).unwrap();
+        assert!(!*session.get_follow_theme());
+        assert_eq!(session.get_colors().background, [0.1, 0.1, 0.2]);
+    }
 
-        None
+    #[test]
+    fn newer_session_rejected() {
+        let newer_session = format!(r#"{{"version":{},"source":"Stdin"}}"#, SESSION_VERSION + 1);
+        assert!(parse_session(&newer_session).is_err());
+        assert!(parse_session("[]").is_err());
     }
 }
@@ -0,0 +1,122 @@
+use std::error::Error;
+use std::path::PathBuf;
+use app_dirs2::{AppDataType, get_app_root};
+use egui::Visuals;
+use serde::{Deserialize, Serialize};
+use crate::APP_INFO;
+use crate::highlight::{Colors, rgb_to_color32};
+
+const SETTINGS_FILENAME: &str = "settings.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum Theme {
+    /// Dark or light, as the system one
+    #[default]
+    System,
+    Light,
+    Dark,
+    Custom,
+}
+
+/// Colours of custom theme
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct CustomTheme {
+    /// Based on dark theme rather than light one
+    pub is_dark: bool,
+    pub text: [f32; 3],
+    pub background: [f32; 3],
+    /// Colour of selection and links
+    pub accent: [f32; 3],
+}
+
+impl Default for CustomTheme {
+    fn default() -> Self {
+        Self {
+            is_dark: true,
+            text: [0.85, 0.85, 0.8],
+            background: [0.12, 0.12, 0.16],
+            accent: [0.0, 0.45, 0.75],
+        }
+    }
+}
+
+impl CustomTheme {
+    fn get_visuals(&self) -> Visuals {
+        let mut visuals = if self.is_dark { Visuals::dark() } else { Visuals::light() };
+        visuals.override_text_color = Some(rgb_to_color32(self.text));
+        visuals.panel_fill = rgb_to_color32(self.background);
+        visuals.window_fill = rgb_to_color32(self.background);
+        visuals.selection.bg_fill = rgb_to_color32(self.accent);
+        visuals.hyperlink_color = rgb_to_color32(self.accent);
+        visuals
+    }
+}
+
+/// Application preferences, shared by all tabs
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Size of line text in points
+    pub font_size: f32,
+    /// Space between line rows in points, rows are as high as the font
+    pub line_spacing: f32,
+    /// Colours of new sessions, none for the theme ones
+    pub default_colors: Option<Colors>,
+    /// Change highlight colours made for background of another lightness to stay readable
+    pub adjust_contrast: bool,
+    /// Most paths kept in recents
+    pub max_recents: usize,
+    /// Most lines kept by new sessions, older lines of opened files are dropped too
+    pub default_max_lines: usize,
+    pub theme: Theme,
+    pub custom_theme: CustomTheme,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            font_size: 12.0,
+            line_spacing: 3.0,
+            default_colors: None,
+            adjust_contrast: true,
+            max_recents: 10,
+            default_max_lines: 1_000_000,
+            theme: Theme::System,
+            custom_theme: CustomTheme::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn try_load() -> Self {
+        Self::get_save_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|settings_json| serde_json::from_str(&settings_json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Ok(path) = Self::get_save_path() {
+            let settings_json = serde_json::to_string(&self).unwrap_or("{}".to_string());
+            let _ = std::fs::write(path, settings_json);
+        }
+    }
+
+    /// Visuals of the theme, system one is dark unless told otherwise
+    pub fn get_visuals(&self, system_theme: Option<eframe::Theme>) -> Visuals {
+        match self.theme {
+            Theme::System => match system_theme {
+                Some(eframe::Theme::Light) => Visuals::light(),
+                _ => Visuals::dark(),
+            },
+            Theme::Light => Visuals::light(),
+            Theme::Dark => Visuals::dark(),
+            Theme::Custom => self.custom_theme.get_visuals(),
+        }
+    }
+
+    fn get_save_path() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(get_app_root(AppDataType::UserData, &APP_INFO)?.join(SETTINGS_FILENAME))
+    }
+}
@@ -0,0 +1,55 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// Path argument telling to follow standard input, e.g. `some_cmd | tailor -`
+const STDIN_PATH: &str = "-";
+
+/// What is followed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// File or folder
+    File(PathBuf),
+    /// Output of shell command
+    Command(String),
+    Stdin,
+}
+
+impl Source {
+    /// Source named by command line argument, `-` for standard input
+    pub fn from_arg(arg: &str) -> Self {
+        if arg == STDIN_PATH {
+            Source::Stdin
+        } else {
+            Source::File(PathBuf::from(arg))
+        }
+    }
+
+    pub fn get_path(&self) -> Option<&Path> {
+        match self {
+            Source::File(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// File or folder name, or the whole command
+    pub fn get_title(&self) -> String {
+        match self {
+            Source::File(path) => match path.file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => path.display().to_string(),
+            },
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Command(command) => write!(f, "$ {}", command),
+            Source::Stdin => write!(f, "Standard input"),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use app_dirs2::{AppDataType, get_app_root};
+use chrono::{DateTime, Utc};
+use egui::{Align, Color32, Context, Key, Layout, TextEdit, Ui};
+use regex::Regex;
+use tailor::{CommandClient, Message, PipeClient, Tailor};
+use crate::APP_INFO;
+use crate::bookmark::LineKey;
+use crate::lines::LinesState;
+use crate::panels::main::MainPanel;
+use crate::profile::Profiles;
+use crate::search::Search;
+use crate::session::Session;
+use crate::settings::Settings;
+use crate::source::Source;
+
+const TABS_FILENAME: &str = "tabs.json";
+const FILTER_HELP: &str = "Words, \"phrases\" and /regex/ combined with AND, OR, NOT and parentheses.\n\
+    Parsed lines can be compared by fields, e.g. level>=warn latency_ms>500 user=42,\n\
+    and by time, e.g. time>=2023-10-11T22:00 or time>-15m";
+
+struct TailorClient {
+    client_id: i32,
+    #[allow(dead_code)]
+    handle: std::thread::JoinHandle<()>,
+}
+
+impl TailorClient {
+    fn new(
+        tailor: &mut Tailor,
+        source: &Source,
+        restart_commands: bool,
+        ctx: Context,
+        log_contents: Arc<Mutex<LinesState>>,
+    ) -> Self {
+        let (message_tx, message_rx) = channel();
+        let followed_path = source.get_path().map(|path| path.to_path_buf());
+        let client_handle = std::thread::spawn(move || {
+            while match message_rx.recv_timeout(std::time::Duration::from_secs(2)) {
+                Ok(msg) => {
+                    if let Ok(mut lines) = log_contents.lock() {
+                        match msg {
+                            Message::NewLines(recv_lines) => {
+                                (*lines).add_lines(recv_lines, false);
+                            }
+                            Message::NewErrorLines(recv_lines) => {
+                                (*lines).add_lines(recv_lines, true);
+                            }
+                            Message::LineOffsets(offsets) => {
+                                (*lines).set_next_offsets(offsets);
+                            }
+                            Message::NewFile(path) => {
+                                (*lines).clear_lines();
+                                // Followed file itself is reported when it got truncated
+                                if Some(&path) != followed_path.as_ref() {
+                                    (*lines).set_source(path);
+                                }
+                            }
+                            Message::Exited(code) => {
+                                (*lines).set_exit_code(code);
+                            }
+                        }
+                    }
+
+                    ctx.request_repaint();
+                    true
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => true,
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                    // Watch was stopped or its source has nothing more to read
+                    log::info!("Tailor client disconnected");
+                    false
+                }
+            } {}
+        });
+
+        let client_id = match source {
+            Source::File(path) => tailor.watch(path.clone(), message_tx),
+            Source::Command(command) => {
+                let source = CommandClient::new(command).with_restart(restart_commands);
+                tailor.watch_source(Box::new(source), message_tx)
+            },
+            Source::Stdin => tailor.watch_source(Box::new(PipeClient::stdin()), message_tx),
+        };
+
+        Self {
+            client_id,
+            handle: client_handle,
+        }
+    }
+}
+
+/// Followed file, folder or command, with its own session, lines, filter, search and scroll state
+pub struct Tab {
+    session: Session,
+    client: TailorClient,
+    log_contents: Arc<Mutex<LinesState>>,
+    log_panel: MainPanel,
+    filter_text: String,
+    search_text: String,
+    search: Search,
+    /// Id of bookmarked line navigated to
+    bookmark_line: Option<usize>,
+}
+
+impl Tab {
+    /// Start following the source and load its session
+    pub fn open(tailor: &mut Tailor, source: &Source, restart_commands: bool, profiles: &Profiles, settings: &Settings,
+        ctx: &Context) -> Self {
+        let log_contents = Arc::new(Mutex::new(LinesState::new()));
+        Self {
+            session: Session::new(source.clone(), profiles, settings, &ctx.style().visuals),
+            client: TailorClient::new(tailor, source, restart_commands, ctx.clone(), log_contents.clone()),
+            log_contents,
+            log_panel: MainPanel::new(),
+            filter_text: String::new(),
+            search_text: String::new(),
+            search: Search::new(),
+            bookmark_line: None,
+        }
+    }
+
+    /// Stop following the source and save its session
+    pub fn close(self, tailor: &mut Tailor) {
+        tailor.stop(self.client.client_id);
+        self.session.save();
+    }
+
+    pub fn get_source(&self) -> &Source {
+        self.session.get_source()
+    }
+
+    pub fn get_session(&mut self) -> &mut Session {
+        &mut self.session
+    }
+
+    /// Draw lines into the ui, keyboard shortcuts work only when the tab has focus
+    pub fn draw(&mut self, ui: &mut Ui, has_focus: bool, profiles: &Profiles, settings: &Settings) {
+        self.session.apply_theme(ui.visuals());
+        if let Ok(mut log_contents) = self.log_contents.lock() {
+            let grouping = if *self.session.get_group_records() {
+                Some(self.session.get_record_start_pattern().clone())
+            } else {
+                None
+            };
+            log_contents.set_grouping(grouping.as_deref());
+            log_contents.set_level_words(self.session.get_level_words());
+            log_contents.set_limits(*self.session.get_max_lines(), *self.session.get_max_megabytes() * 1024 * 1024);
+            log_contents.filter_lines(&self.filter_text);
+
+            let profile = self.session.find_profile(profiles);
+            self.log_panel.apply_settings(settings);
+            self.log_panel.draw(
+                ui,
+                &mut self.session,
+                profile,
+                &mut log_contents,
+                &self.search,
+                has_focus,
+            );
+        }
+    }
+
+    /// Time of the first line in view
+    pub fn get_top_time(&self) -> Option<DateTime<Utc>> {
+        let line_id = self.log_panel.get_top_line()?;
+        self.log_contents.lock().ok()?.get_time(line_id)
+    }
+
+    /// Scroll to the shown line with time closest to the given one
+    pub fn align_to_time(&mut self, time: DateTime<Utc>) {
+        let line_id = self.log_contents.lock().ok().and_then(|lines| lines.find_filtered_line_at(time));
+        if let Some(line_id) = line_id {
+            self.log_panel.align_line(line_id);
+        }
+    }
+
+    /// Scroll to the bookmarked line, returns false when it is not shown
+    pub fn scroll_to_key(&mut self, key: &LineKey) -> bool {
+        let line_id = self.log_contents.lock().ok().and_then(|lines| {
+            let line_id = lines.find_line(key)?;
+            lines.get_filtered_lines().binary_search(&line_id).ok().map(|_| line_id)
+        });
+        if let Some(line_id) = line_id {
+            self.bookmark_line = Some(line_id);
+            self.log_panel.scroll_to_line(line_id);
+        }
+        line_id.is_some()
+    }
+
+    /// Scroll to the next or previous bookmarked line shown
+    pub fn go_to_bookmark(&mut self, is_next: bool) {
+        let Ok(lines) = self.log_contents.lock() else {
+            return;
+        };
+        let mut line_ids: Vec<usize> = self.session.get_bookmarks()
+            .iter()
+            .filter_map(|bookmark| lines.find_line(bookmark.get_key()))
+            .filter(|line_id| lines.get_filtered_lines().binary_search(line_id).is_ok())
+            .collect();
+        drop(lines);
+        line_ids.sort();
+
+        let current = self.bookmark_line.or(self.log_panel.get_top_line());
+        let line_id = if is_next {
+            let next = current.map_or(0, |current| line_ids.partition_point(|line_id| *line_id <= current));
+            line_ids.get(next).or(line_ids.first())
+        } else {
+            let previous = current.and_then(|current| line_ids.partition_point(|line_id| *line_id < current).checked_sub(1));
+            previous.and_then(|idx| line_ids.get(idx)).or(line_ids.last())
+        };
+        if let Some(line_id) = line_id {
+            self.bookmark_line = Some(*line_id);
+            self.log_panel.scroll_to_line(*line_id);
+        }
+    }
+
+    /// Bookmarks with their notes and lines around them, as text
+    pub fn export_bookmarks(&mut self, context_lines: usize) -> String {
+        let mut text = format!("Bookmarks of {}\n", self.get_source());
+        let Ok(lines) = self.log_contents.lock() else {
+            return text;
+        };
+        for bookmark in self.session.get_bookmarks().iter_mut() {
+            text.push('\n');
+            if !bookmark.get_note().is_empty() {
+                text.push_str(&format!("# {}\n", bookmark.get_note()));
+            }
+            let Some(line_id) = lines.find_line(bookmark.get_key()) else {
+                text.push_str(&format!("> {}\n", bookmark.get_text()));
+                continue;
+            };
+
+            let first = line_id.saturating_sub(context_lines).max(lines.get_dropped_count());
+            for idx in first..(line_id + context_lines + 1).min(lines.get_end()) {
+                let marker = if idx == line_id { '>' } else { ' ' };
+                text.push_str(&format!("{} {:>6}  {}\n", marker, idx + 1, lines.get_line(idx).map_or("", String::as_str)));
+            }
+        }
+        text
+    }
+
+    /// Path, follow button, filter and search, for the bottom panel
+    pub fn draw_status(&mut self, ui: &mut egui::Ui, ctx: &Context) {
+        ui.horizontal(|ui| {
+            ui.label(self.session.get_source().to_string());
+            if let Some(status) = self.log_contents.lock().ok().and_then(|lines| lines.get_exit_status()) {
+                ui.colored_label(ui.visuals().warn_fg_color, status);
+            }
+            if !self.log_panel.get_is_following() && ui.button("⤓ Follow")
+                .on_hover_text("Scroll to the last lines and keep them in view")
+                .clicked() {
+                self.log_panel.follow();
+            }
+        });
+
+        ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+            if self.search.get_regex().is_some() {
+                let (position, count) = match self.log_contents.lock() {
+                    Ok(lines) => self.search.get_position(&lines),
+                    Err(_) => (None, 0),
+                };
+                if ui.button("▼").on_hover_text("Next match (Enter, F3)").clicked() {
+                    self.go_to_match(ctx, true);
+                }
+                if ui.button("▲").on_hover_text("Previous match (Shift+Enter, Shift+F3)").clicked() {
+                    self.go_to_match(ctx, false);
+                }
+                match position {
+                    Some(position) => ui.label(format!("{} / {}", position, count)),
+                    None => ui.label(format!("{} found", count)),
+                };
+            }
+            let search_edit = ui.add(
+                TextEdit::singleline(&mut self.search_text)
+                    .hint_text("Search")
+                    .desired_width(120.0),
+            );
+            if search_edit.changed() {
+                if !self.search_text.is_empty() {
+                    if let Ok(regex) =
+                        Regex::new(format!(r"(?i){}", &self.search_text).as_str())
+                    {
+                        self.search.set_regex(Some(regex), self.log_contents.clone(), ctx.clone());
+                    }
+                } else {
+                    self.search.set_regex(None, self.log_contents.clone(), ctx.clone());
+                }
+            }
+            if search_edit.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                let is_next = !ui.input(|i| i.modifiers.shift);
+                self.go_to_match(ctx, is_next);
+                search_edit.request_focus();
+            }
+            if let Some(is_next) = ui.input(|i| i.key_pressed(Key::F3).then_some(!i.modifiers.shift)) {
+                self.go_to_match(ctx, is_next);
+            }
+            if let Some(is_next) = ui.input(|i| i.key_pressed(Key::F2).then_some(!i.modifiers.shift)) {
+                self.go_to_bookmark(is_next);
+                ctx.request_repaint();
+            }
+            let query_error = self.log_contents.lock().ok()
+                .and_then(|lines| lines.get_query_error().map(String::from));
+            let mut filter_edit = TextEdit::singleline(&mut self.filter_text)
+                .hint_text("Filter")
+                .desired_width(200.0);
+            if query_error.is_some() {
+                filter_edit = filter_edit.text_color(Color32::RED);
+            }
+            ui.add(filter_edit).on_hover_text(query_error.unwrap_or(FILTER_HELP.to_string()));
+        });
+    }
+
+    /// Scroll to the next or previous search match
+    fn go_to_match(&mut self, ctx: &Context, is_next: bool) {
+        let line_id = match self.log_contents.lock() {
+            Ok(lines) if is_next => self.search.next(&lines),
+            Ok(lines) => self.search.previous(&lines),
+            Err(_) => None,
+        };
+        if let Some(line_id) = line_id {
+            self.log_panel.scroll_to_line(line_id);
+            ctx.request_repaint();
+        }
+    }
+}
+
+/// Files and folders of tabs open when the app was closed. Standard input and commands
+/// are not restored, they can not be read again or should not run without asking.
+pub fn try_load_open_tabs() -> Vec<Source> {
+    if let Ok(data_path) = get_app_root(AppDataType::UserData, &APP_INFO) {
+        if let Ok(loaded_tabs) = std::fs::read_to_string(data_path.join(TABS_FILENAME)) {
+            if let Ok(tabs) = serde_json::from_str(&loaded_tabs) {
+                return tabs;
+            }
+        }
+    }
+
+    vec![]
+}
+
+pub fn try_save_open_tabs(tabs: &[Tab]) {
+    if let Ok(data_path) = get_app_root(AppDataType::UserData, &APP_INFO) {
+        let sources: Vec<&Source> = tabs.iter()
+            .map(Tab::get_source)
+            .filter(|source| matches!(source, Source::File(_)))
+            .collect();
+        let tabs_json = serde_json::to_string(&sources).unwrap_or("[]".to_string());
+        let _ = std::fs::write(data_path.join(TABS_FILENAME), tabs_json);
+    }
+}
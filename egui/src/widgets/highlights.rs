@@ -0,0 +1,128 @@
+use egui::color_picker::color_edit_button_rgb;
+use egui::{Color32, ComboBox, Label, Sense, Stroke, TextEdit};
+use tailor::LogLevel;
+use crate::highlight::{Colors, Highlight, HighlightScope};
+
+/// Editable list of highlights, reordered by dragging
+#[derive(Default)]
+pub struct HighlightsEdit {
+    /// Index of highlight being dragged to another place
+    dragged_highlight: Option<usize>,
+}
+
+impl HighlightsEdit {
+    pub fn draw(&mut self, ui: &mut egui::Ui, id_source: &str, highlights: &mut Vec<Highlight>) {
+        let mut remove_at = None;
+        let mut row_rects = vec![];
+        for (index, highlight) in highlights.iter_mut().enumerate() {
+            let row = ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    let handle = ui.add(Label::new("☰").sense(Sense::drag()))
+                        .on_hover_text("Drag to change order, the first matching highlight is used");
+                    if handle.drag_started() {
+                        self.dragged_highlight = Some(index);
+                    }
+                    ui.checkbox(highlight.get_is_enabled(), "")
+                        .on_hover_text("Enabled");
+                    if let Some(mut level) = highlight.get_level() {
+                        ComboBox::from_id_source((id_source, "highlight_level", index))
+                            .selected_text(format!("Level {}", level))
+                            .width(120.0)
+                            .show_ui(ui, |ui| {
+                                for option in LogLevel::ALL {
+                                    ui.selectable_value(&mut level, option, option.name());
+                                }
+                            });
+                        highlight.set_level(level);
+                    } else {
+                        let is_invalid = highlight.get_regex_error().is_some();
+                        let mut pattern_edit = TextEdit::singleline(highlight.get_pattern())
+                            .hint_text("Regex Pattern")
+                            .desired_width(120.0);
+                        if is_invalid {
+                            pattern_edit = pattern_edit.text_color(Color32::RED);
+                        }
+                        if ui.add(pattern_edit).changed() {
+                            highlight.update_regex();
+                        }
+                    }
+                    color_edit_button_rgb(ui, &mut highlight.get_mut_colors().foreground);
+                    color_edit_button_rgb(ui, &mut highlight.get_mut_colors().background);
+                    if ui.button("🗑").clicked() {
+                        remove_at = Some(index);
+                    }
+                });
+                if highlight.get_level().is_none() {
+                    ui.horizontal(|ui| {
+                        ui.add_space(16.0);
+                        let mut is_changed = ui.toggle_value(highlight.get_is_case_sensitive(), "Aa")
+                            .on_hover_text("Match case")
+                            .changed();
+                        is_changed |= ui.toggle_value(highlight.get_is_whole_word(), "\\b")
+                            .on_hover_text("Match whole words only")
+                            .changed();
+                        if is_changed {
+                            highlight.update_regex();
+                        }
+                        let mut scope = highlight.get_scope();
+                        ui.selectable_value(&mut scope, HighlightScope::Line, "Line")
+                            .on_hover_text("Colour whole lines with matches");
+                        ui.selectable_value(&mut scope, HighlightScope::Match, "Match")
+                            .on_hover_text("Colour only matching text");
+                        highlight.set_scope(scope);
+                    });
+                    for (group, colors) in highlight.get_group_colors().iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.add_space(16.0);
+                            let mut is_colored = colors.is_some();
+                            if ui.checkbox(&mut is_colored, format!("Group {}", group + 1))
+                                .on_hover_text("Colour the capture group instead of whole match")
+                                .changed() {
+                                *colors = is_colored.then(Colors::default);
+                            }
+                            if let Some(colors) = colors {
+                                color_edit_button_rgb(ui, &mut colors.foreground);
+                                color_edit_button_rgb(ui, &mut colors.background);
+                            }
+                        });
+                    }
+                    if let Some(error) = highlight.get_regex_error() {
+                        ui.colored_label(Color32::RED, error);
+                    }
+                }
+            });
+            row_rects.push(row.response.rect);
+        }
+        if let Some(dragged) = self.dragged_highlight {
+            if let Some(pointer) = ui.ctx().pointer_interact_pos() {
+                // Dragged highlight goes before the first row below the pointer
+                let target = row_rects.iter().filter(|rect| rect.center().y < pointer.y).count();
+                let y = row_rects.get(target).map_or_else(
+                    || row_rects.last().map_or(pointer.y, |rect| rect.bottom()),
+                    |rect| rect.top());
+                ui.painter().hline(ui.max_rect().x_range(), y, Stroke::new(2.0, ui.visuals().selection.bg_fill));
+                if ui.input(|i| i.pointer.any_released()) {
+                    let target = if target > dragged { target - 1 } else { target };
+                    if dragged < highlights.len() && target < highlights.len() {
+                        let highlight = highlights.remove(dragged);
+                        highlights.insert(target, highlight);
+                    }
+                    self.dragged_highlight = None;
+                }
+            } else {
+                self.dragged_highlight = None;
+            }
+        }
+        if let Some(index) = remove_at {
+            highlights.remove(index);
+        }
+        ui.horizontal(|ui| {
+            if ui.button(" + ").on_hover_text("Add pattern highlight").clicked() {
+                highlights.push(Highlight::default());
+            }
+            if ui.button(" + Level ").on_hover_text("Add log level highlight").clicked() {
+                highlights.push(Highlight::for_level(LogLevel::Info, Colors::default()));
+            }
+        });
+    }
+}
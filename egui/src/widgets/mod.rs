@@ -1 +1,2 @@
+pub mod highlights;
 pub mod recents;
\ No newline at end of file
@@ -1,20 +1,23 @@
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use app_dirs2::{AppDataType, get_app_root};
 use egui::ComboBox;
 use crate::APP_INFO;
+use crate::source::Source;
 
 const RECENTS_FILENAME: &str = "recents.json";
 
 pub struct RecentsBox {
-    recents: Vec<String>,
-    selected_recent: String,
+    recents: Vec<Source>,
+    selected_recent: Option<Source>,
+    is_dirty: bool,
 }
 
 impl Default for RecentsBox {
     fn default() -> Self {
         Self {
             recents: Self::try_load_recent(),
-            selected_recent: String::new(),
+            selected_recent: None,
+            is_dirty: false,
         }
     }
 }
@@ -22,32 +25,43 @@ impl Default for RecentsBox {
 impl RecentsBox {
     pub fn draw(&mut self, ui: &mut egui::Ui) {
         ComboBox::from_label("🔁")
-            .selected_text(&self.selected_recent)
+            .selected_text(self.selected_recent.as_ref().map(Source::to_string).unwrap_or_default())
             .width(300.0)
             .show_ui(ui, |ui| {
                 for recent in &self.recents {
-                    ui.selectable_value(&mut self.selected_recent, recent.clone(), recent);
+                    if ui.selectable_value(&mut self.selected_recent, Some(recent.clone()), recent.to_string()).changed() {
+                        self.is_dirty = true;
+                    }
                 }
             });
     }
 
-    pub fn is_dirty(&self, prev_path: &Path) -> bool {
-        self.selected_recent != prev_path.display().to_string()
+    pub fn is_dirty(&self, prev_source: Option<&Source>) -> bool {
+        self.is_dirty && self.selected_recent.as_ref() != prev_source
+    }
+
+    /// Show no recent as selected, e.g. when following something that is not kept in recents
+    pub fn clear_selected_recent(&mut self) {
+        self.selected_recent = None;
+        self.is_dirty = false;
     }
 
-    pub fn update_recents(&mut self, path: &Path) {
-        self.recents = self.recents.clone().into_iter().filter(|recent| *recent != path.display().to_string()).collect();
-        self.recents.insert(0, path.display().to_string());
-        self.recents.truncate(10);
-        self.selected_recent = path.display().to_string();
+    /// Put the source first in recents, keeping at most given number of them
+    pub fn update_recents(&mut self, source: &Source, max_recents: usize) {
+        self.recents.retain(|recent| recent != source);
+        self.recents.insert(0, source.clone());
+        self.recents.truncate(max_recents);
+        self.selected_recent = Some(source.clone());
+        self.is_dirty = false;
         Self::try_save_recents(&self.recents);
     }
 
-    pub fn get_selected_recent_path(&self) -> PathBuf {
-        PathBuf::from(&self.selected_recent)
+    pub fn get_selected_recent(&self) -> Option<Source> {
+        self.selected_recent.clone()
     }
 
-    fn try_load_recent() -> Vec<String> {
+    /// Recents saved as file paths, before commands could be followed, are read too
+    fn try_load_recent() -> Vec<Source> {
         if let Ok(data_path) = get_app_root(AppDataType::UserData, &APP_INFO) {
             if data_path.exists() {
                 let recents_path = data_path.join(RECENTS_FILENAME);
@@ -56,8 +70,11 @@ impl RecentsBox {
                     if let Ok(recents) = serde_json::from_str(&loaded_recents) {
                         return recents;
                     }
+                    if let Ok(recents) = serde_json::from_str::<Vec<PathBuf>>(&loaded_recents) {
+                        return recents.into_iter().map(Source::File).collect();
+                    }
                 } else {
-                    let recents: Vec<String> = vec![];
+                    let recents: Vec<Source> = vec![];
                     let recents_json = serde_json::to_string(&recents).unwrap_or("{}".to_string());
                     let _ = std::fs::write(&recents_path, recents_json);
                 }
@@ -67,7 +84,7 @@ impl RecentsBox {
         vec![]
     }
 
-    fn try_save_recents(recents: &Vec<String>) {
+    fn try_save_recents(recents: &Vec<Source>) {
         if let Ok(data_path) = get_app_root(AppDataType::UserData, &APP_INFO) {
             let recents_path = data_path.join(RECENTS_FILENAME);
             let recents_json = serde_json::to_string(recents).unwrap_or("{}".to_string());
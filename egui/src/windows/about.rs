@@ -1,4 +1,4 @@
-use egui::{Color32, Context, FontId};
+use egui::{Context, FontId};
 use egui::text::LayoutJob;
 
 #[derive(Default)]
@@ -40,7 +40,7 @@ impl AboutWindow {
         let title_job = LayoutJob::simple(
             title.to_owned(),
             FontId::proportional(24.0),
-            Color32::BLACK,
+            ui.visuals().strong_text_color(),
             120.0
         );
 
@@ -0,0 +1,63 @@
+use egui::{Context, TextEdit};
+
+#[derive(Default)]
+pub struct CommandWindow {
+    is_visible: bool,
+    command: String,
+    restart_on_exit: bool,
+    picked_command: Option<String>,
+}
+
+impl CommandWindow {
+    pub fn get_is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    pub fn toggle_is_visible(&mut self) {
+        self.is_visible = !self.is_visible;
+    }
+
+    pub fn get_restart_on_exit(&self) -> bool {
+        self.restart_on_exit
+    }
+
+    /// Command user asked to run, if any since last call
+    pub fn take_picked_command(&mut self) -> Option<String> {
+        self.picked_command.take()
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if !self.is_visible {
+            return;
+        }
+
+        let mut is_visible_update = true;
+
+        egui::Window::new("Open command")
+            .collapsible(false)
+            .resizable(false)
+            .default_width(400.0)
+            .title_bar(true)
+            .open(&mut is_visible_update)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+
+        self.is_visible = is_visible_update && self.is_visible;
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let command_edit = TextEdit::singleline(&mut self.command)
+            .hint_text("journalctl -f")
+            .desired_width(380.0);
+        let response = ui.add(command_edit);
+        let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        ui.checkbox(&mut self.restart_on_exit, "Restart on exit");
+        ui.horizontal(|ui| {
+            if (ui.button("Run").clicked() || entered) && !self.command.trim().is_empty() {
+                self.picked_command = Some(self.command.trim().to_string());
+                self.is_visible = false;
+            }
+        });
+    }
+}
@@ -1,14 +1,25 @@
 use egui::Context;
+use crate::profile::Profiles;
+use crate::settings::Settings;
 
 pub mod about;
+pub mod command;
+pub mod preferences;
+pub mod profiles;
 
 #[derive(Default)]
 pub struct Windows {
     pub about: about::AboutWindow,
+    pub command: command::CommandWindow,
+    pub preferences: preferences::PreferencesWindow,
+    pub profiles: profiles::ProfilesWindow,
 }
 
 impl Windows {
-    pub fn draw(&mut self, ctx: &Context) {
+    pub fn draw(&mut self, ctx: &Context, profiles: &mut Profiles, settings: &mut Settings) {
         self.about.show(ctx);
+        self.command.show(ctx);
+        self.preferences.show(ctx, settings);
+        self.profiles.show(ctx, profiles);
     }
 }
\ No newline at end of file
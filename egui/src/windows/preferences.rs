@@ -0,0 +1,110 @@
+use egui::color_picker::color_edit_button_rgb;
+use egui::{Context, DragValue};
+use crate::highlight::Colors;
+use crate::settings::{Settings, Theme};
+
+#[derive(Default)]
+pub struct PreferencesWindow {
+    is_visible: bool,
+}
+
+impl PreferencesWindow {
+    pub fn get_is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    pub fn toggle_is_visible(&mut self) {
+        self.is_visible = !self.is_visible;
+    }
+
+    /// Show the window, applying and saving changed settings right away
+    pub fn show(&mut self, ctx: &Context, settings: &mut Settings) {
+        if !self.is_visible {
+            return;
+        }
+
+        let mut is_visible_update = true;
+        let previous = settings.clone();
+
+        egui::Window::new("Preferences")
+            .collapsible(false)
+            .resizable(false)
+            .default_width(300.0)
+            .title_bar(true)
+            .open(&mut is_visible_update)
+            .show(ctx, |ui| {
+                Self::ui(ui, settings);
+            });
+
+        if *settings != previous {
+            settings.save();
+        }
+        self.is_visible = is_visible_update && self.is_visible;
+    }
+
+    fn ui(ui: &mut egui::Ui, settings: &mut Settings) {
+        egui::Grid::new("preferences").num_columns(2).show(ui, |ui| {
+            ui.label("Theme:");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut settings.theme, Theme::System, "System");
+                ui.selectable_value(&mut settings.theme, Theme::Light, "Light");
+                ui.selectable_value(&mut settings.theme, Theme::Dark, "Dark");
+                ui.selectable_value(&mut settings.theme, Theme::Custom, "Custom");
+            });
+            ui.end_row();
+
+            if settings.theme == Theme::Custom {
+                let custom_theme = &mut settings.custom_theme;
+                ui.label("");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut custom_theme.is_dark, "Dark");
+                    ui.label("Text");
+                    color_edit_button_rgb(ui, &mut custom_theme.text);
+                    ui.label("Background");
+                    color_edit_button_rgb(ui, &mut custom_theme.background);
+                    ui.label("Accent");
+                    color_edit_button_rgb(ui, &mut custom_theme.accent);
+                });
+                ui.end_row();
+            }
+
+            ui.label("Font size:");
+            ui.add(DragValue::new(&mut settings.font_size).clamp_range(6.0..=48.0).speed(0.1).suffix(" pt"));
+            ui.end_row();
+
+            ui.label("Line spacing:");
+            ui.add(DragValue::new(&mut settings.line_spacing).clamp_range(0.0..=32.0).speed(0.1).suffix(" pt"))
+                .on_hover_text("Space between lines, added to the height of the font");
+            ui.end_row();
+
+            ui.label("New session colours:");
+            ui.horizontal(|ui| {
+                let mut is_theme_colors = settings.default_colors.is_none();
+                if ui.checkbox(&mut is_theme_colors, "Theme").changed() {
+                    settings.default_colors = (!is_theme_colors).then(|| Colors::from_visuals(ui.visuals()));
+                }
+                if let Some(colors) = &mut settings.default_colors {
+                    ui.label("Text");
+                    color_edit_button_rgb(ui, &mut colors.foreground);
+                    ui.label("Background");
+                    color_edit_button_rgb(ui, &mut colors.background);
+                }
+            });
+            ui.end_row();
+
+            ui.label("Highlights:");
+            ui.checkbox(&mut settings.adjust_contrast, "Adjust contrast")
+                .on_hover_text("Keep highlights made for dark background readable on light one and the other way round");
+            ui.end_row();
+
+            ui.label("New session lines:");
+            ui.add(DragValue::new(&mut settings.default_max_lines).speed(1000).suffix(" lines"))
+                .on_hover_text("Lines kept by new sessions, older lines of opened files are dropped too. 0 means no limit.");
+            ui.end_row();
+
+            ui.label("Recents:");
+            ui.add(DragValue::new(&mut settings.max_recents).clamp_range(1..=100).suffix(" paths"));
+            ui.end_row();
+        });
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use egui::{Color32, Context, TextEdit};
+use crate::profile::{Profile, Profiles};
+use crate::widgets::highlights::HighlightsEdit;
+
+/// Editor of highlight profiles shared by sessions
+pub struct ProfilesWindow {
+    is_visible: bool,
+    selected: usize,
+    highlights_edit: HighlightsEdit,
+    /// Profile files picked to import
+    import_channel: (Sender<PathBuf>, Receiver<PathBuf>),
+    import_error: Option<String>,
+    /// Imported profile waiting for confirmation to replace the one with the same name
+    pending_import: Option<(usize, Profile)>,
+}
+
+impl Default for ProfilesWindow {
+    fn default() -> Self {
+        Self {
+            is_visible: false,
+            selected: 0,
+            highlights_edit: HighlightsEdit::default(),
+            import_channel: channel(),
+            import_error: None,
+            pending_import: None,
+        }
+    }
+}
+
+impl ProfilesWindow {
+    pub fn get_is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    pub fn toggle_is_visible(&mut self) {
+        self.is_visible = !self.is_visible;
+    }
+
+    pub fn show(&mut self, ctx: &Context, profiles: &mut Profiles) {
+        if let Ok(path) = self.import_channel.1.try_recv() {
+            self.import(&path, profiles);
+        }
+
+        if !self.is_visible {
+            return;
+        }
+
+        let mut is_visible_update = true;
+
+        egui::Window::new("Highlight profiles")
+            .collapsible(false)
+            .resizable(true)
+            .default_width(400.0)
+            .default_height(400.0)
+            .title_bar(true)
+            .open(&mut is_visible_update)
+            .show(ctx, |ui| {
+                self.ui(ui, profiles);
+            });
+
+        if !is_visible_update {
+            profiles.save();
+        }
+        self.is_visible = is_visible_update && self.is_visible;
+    }
+
+    /// Add profile read from the file, the one with the same name is replaced once confirmed
+    fn import(&mut self, path: &Path, profiles: &mut Profiles) {
+        match Profile::import(path) {
+            Ok(profile) => {
+                let existing = profiles.iter().position(|existing| existing.get_name() == profile.get_name());
+                match existing {
+                    Some(idx) => {
+                        self.selected = idx;
+                        self.pending_import = Some((idx, profile));
+                    },
+                    None => {
+                        self.selected = profiles.add(profile);
+                        profiles.save();
+                    },
+                }
+                self.import_error = None;
+            },
+            Err(error) => self.import_error = Some(format!("Failed to import {}: {}", path.display(), error)),
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, profiles: &mut Profiles) {
+        ui.horizontal_wrapped(|ui| {
+            for (idx, name) in profiles.get_names().into_iter().enumerate() {
+                ui.selectable_value(&mut self.selected, idx, name);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button(" + ").on_hover_text("Add profile").clicked() {
+                let names = profiles.get_names();
+                let name = (1..)
+                    .map(|number| format!("Profile {}", number))
+                    .find(|name| !names.contains(name))
+                    .unwrap_or_default();
+                self.selected = profiles.add(Profile::new(name));
+            }
+            if ui.button("Import…").on_hover_text("Add profile from file, asking before replacing the one with the same name").clicked() {
+                let sender = self.import_channel.0.clone();
+                let task = rfd::AsyncFileDialog::new().add_filter("Profile", &["json"]).pick_file();
+                std::thread::spawn(move || futures::executor::block_on(async move {
+                    if let Some(file) = task.await {
+                        let _ = sender.send(file.path().to_path_buf());
+                    }
+                }));
+            }
+            if ui.button("Save").clicked() {
+                profiles.save();
+            }
+        });
+        if let Some(error) = profiles.get_load_error() {
+            ui.colored_label(Color32::RED, format!("Saved profiles could not be loaded: {}", error));
+        }
+        if let Some(error) = &self.import_error {
+            ui.colored_label(Color32::RED, error);
+        }
+        if let Some((idx, profile)) = self.pending_import.take() {
+            ui.horizontal(|ui| {
+                ui.label(format!("Profile {} exists, replace it with the imported one?", profile.get_name()));
+                if ui.button("Replace").clicked() {
+                    profiles.replace(idx, profile);
+                    profiles.save();
+                } else if !ui.button("Cancel").clicked() {
+                    self.pending_import = Some((idx, profile));
+                }
+            });
+        }
+        ui.separator();
+
+        let mut remove_selected = false;
+        if let Some(profile) = profiles.get_profiles().get_mut(self.selected) {
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.add(TextEdit::singleline(profile.get_mut_name()).desired_width(160.0))
+                    .on_hover_text("Sessions keep using the profile when it is renamed");
+                if ui.button("Export…").on_hover_text("Save the profile to file, e.g. to share it in a repository").clicked() {
+                    let profile_json = profile.export().unwrap_or("{}".to_string());
+                    let task = rfd::AsyncFileDialog::new()
+                        .set_file_name(&format!("{}.json", profile.get_name()))
+                        .save_file();
+                    std::thread::spawn(move || futures::executor::block_on(async move {
+                        if let Some(file) = task.await {
+                            if let Err(error) = std::fs::write(file.path(), profile_json) {
+                                log::error!("Failed to export profile: {}", error);
+                            }
+                        }
+                    }));
+                }
+                remove_selected = ui.button("🗑").on_hover_text("Delete profile").clicked();
+            });
+            ui.add(TextEdit::singleline(profile.get_file_patterns())
+                .hint_text("File names, e.g. *.log, nginx*")
+                .desired_width(f32::INFINITY))
+                .on_hover_text("New sessions of matching files use the profile");
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                self.highlights_edit.draw(ui, "profile", profile.get_mut_highlights());
+            });
+        }
+        if remove_selected {
+            self.pending_import = None;
+            profiles.get_profiles().remove(self.selected);
+            self.selected = self.selected.saturating_sub(1);
+            profiles.save();
+        }
+    }
+}